@@ -1,9 +1,119 @@
-use sdl2::render::Texture;
+use std::time::Duration;
 
-use crate::texture_manager::SpriteTable;
+use serde::Deserialize;
 
-pub(crate) struct Animation<'a> {
-    texture: Texture<'a>,
-    sprites: SpriteTable,
-    //Bruh
+use crate::texture_manager::SpriteRegion;
+
+// what happens once the last frame has been shown
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LoopMode {
+    #[default]
+    Loop,
+    // plays the frames forwards and then backwards
+    PingPong,
+    // stays on the last frame
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Animation {
+    // never empty
+    pub(crate) frames: Vec<SpriteRegion>,
+    pub(crate) frame_duration: Duration,
+    pub(crate) mode: LoopMode,
+}
+
+impl Animation {
+    // the frame shown after the animation has been playing for `elapsed`
+    pub(crate) fn frame(&self, elapsed: Duration) -> SpriteRegion {
+        let count = self.frames.len();
+        let shown = (elapsed.as_millis() / self.frame_duration.as_millis().max(1)) as usize;
+
+        let index = match self.mode {
+            LoopMode::Loop => shown % count,
+            LoopMode::Once => shown.min(count - 1),
+            LoopMode::PingPong if count == 1 => 0,
+            LoopMode::PingPong => {
+                let period = 2 * (count - 1);
+                let i = shown % period;
+                if i < count {
+                    i
+                } else {
+                    period - i
+                }
+            }
+        };
+        self.frames[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::rect::Rect;
+
+    use super::*;
+    use crate::texture_manager::DEFAULT_ATLAS_ID;
+
+    fn numbered(count: usize, frame_ms: u64, mode: LoopMode) -> Animation {
+        let frames = (0..count)
+            .map(|i| SpriteRegion {
+                atlas: DEFAULT_ATLAS_ID,
+                rect: Rect::new(i as i32, 0, 1, 1),
+            })
+            .collect();
+        Animation {
+            frames,
+            frame_duration: Duration::from_millis(frame_ms),
+            mode,
+        }
+    }
+
+    // the indices of the frames shown every 100 ms, starting at 0
+    fn indices(animation: &Animation, count: u64) -> Vec<i32> {
+        (0..count)
+            .map(|i| animation.frame(Duration::from_millis(i * 100)).rect.x())
+            .collect()
+    }
+
+    #[test]
+    fn loop_starts_over() {
+        let animation = numbered(3, 100, LoopMode::Loop);
+        assert_eq!(indices(&animation, 7), [0, 1, 2, 0, 1, 2, 0]);
+        // a frame is shown for its whole duration
+        assert_eq!(animation.frame(Duration::from_millis(199)).rect.x(), 1);
+    }
+
+    #[test]
+    fn ping_pong_goes_back_without_repeating_the_ends() {
+        let animation = numbered(3, 100, LoopMode::PingPong);
+        assert_eq!(indices(&animation, 9), [0, 1, 2, 1, 0, 1, 2, 1, 0]);
+
+        let two = numbered(2, 100, LoopMode::PingPong);
+        assert_eq!(indices(&two, 5), [0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn once_stays_on_the_last_frame() {
+        let animation = numbered(3, 100, LoopMode::Once);
+        assert_eq!(indices(&animation, 6), [0, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn single_frames_and_short_durations() {
+        for mode in [LoopMode::Loop, LoopMode::PingPong, LoopMode::Once] {
+            assert_eq!(indices(&numbered(1, 100, mode), 4), [0, 0, 0, 0]);
+        }
+        // a zero duration is treated as one millisecond instead of dividing by zero
+        let zero = numbered(3, 0, LoopMode::Loop);
+        assert_eq!(zero.frame(Duration::ZERO).rect.x(), 0);
+        assert_eq!(zero.frame(Duration::from_millis(1)).rect.x(), 1);
+        assert_eq!(zero.frame(Duration::from_millis(5)).rect.x(), 2);
+        // durations below a millisecond count as one millisecond as well
+        let short = Animation {
+            frame_duration: Duration::from_micros(10),
+            ..numbered(2, 0, LoopMode::PingPong)
+        };
+        assert_eq!(short.frame(Duration::from_millis(3)).rect.x(), 1);
+    }
 }
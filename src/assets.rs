@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// name of the atlas inside an asset root
+pub(crate) const ATLAS_FILE: &str = "texture.png";
+// the atlas shipped with the crate, so that it works wherever it has been installed to
+pub(crate) const DEFAULT_ATLAS: &[u8] = include_bytes!("../assets/texture.png");
+// how often the watched file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// watches a file by polling its modification time
+pub(crate) struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        FileWatcher {
+            modified: modified(&path),
+            path,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // true the first time it is called after the file has been written
+    pub(crate) fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let modified = modified(&self.path);
+        // a file that is being replaced can be missing for a moment
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+
+    // reports the file as changed again at the next check, e.g. after it failed to load
+    pub(crate) fn retry(&mut self) {
+        self.modified = None;
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
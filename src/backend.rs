@@ -0,0 +1,240 @@
+use sdl2::image::LoadTexture;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::surface::{Surface, SurfaceContext};
+use sdl2::video::{Window, WindowContext};
+use specs::World;
+
+use std::path::Path;
+
+use crate::camera::Camera;
+use crate::capture::read_canvas;
+use crate::renderer::render_sprites;
+
+// the canvas the frames are drawn to, either a real window or an offscreen software surface
+pub(crate) enum RenderCanvas {
+    Window(Canvas<Window>),
+    Headless(Canvas<Surface<'static>>),
+}
+
+// the texture creators are kept apart from the canvas so that a texture can borrow the creator
+// while the canvas is being drawn on
+pub(crate) enum RenderTextureCreator {
+    Window(TextureCreator<WindowContext>),
+    Headless(TextureCreator<SurfaceContext<'static>>),
+}
+
+impl RenderCanvas {
+    pub(crate) fn output_size(&self) -> (u32, u32) {
+        match self {
+            RenderCanvas::Window(canvas) => canvas.output_size().unwrap(),
+            RenderCanvas::Headless(canvas) => canvas.output_size().unwrap(),
+        }
+    }
+
+    pub(crate) fn is_headless(&self) -> bool {
+        matches!(self, RenderCanvas::Headless(_))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        match self {
+            RenderCanvas::Window(canvas) => canvas.clear(),
+            RenderCanvas::Headless(canvas) => canvas.clear(),
+        }
+    }
+
+    pub(crate) fn present(&mut self) {
+        match self {
+            RenderCanvas::Window(canvas) => canvas.present(),
+            RenderCanvas::Headless(canvas) => canvas.present(),
+        }
+    }
+
+    // returns width, height and the RGBA32 pixels of what has been drawn so far
+    pub(crate) fn read_pixels(&self) -> Result<(u32, u32, Vec<u8>), String> {
+        match self {
+            RenderCanvas::Window(canvas) => read_canvas(canvas),
+            RenderCanvas::Headless(canvas) => read_canvas(canvas),
+        }
+    }
+
+    pub(crate) fn render_target_supported(&self) -> bool {
+        match self {
+            RenderCanvas::Window(canvas) => canvas.render_target_supported(),
+            RenderCanvas::Headless(canvas) => canvas.render_target_supported(),
+        }
+    }
+
+    pub(crate) fn copy(&mut self, texture: &Texture, dst: Rect) -> Result<(), String> {
+        match self {
+            RenderCanvas::Window(canvas) => canvas.copy(texture, None, dst),
+            RenderCanvas::Headless(canvas) => canvas.copy(texture, None, dst),
+        }
+    }
+
+    // fills the rects with a color, blended with what has been drawn below them
+    pub(crate) fn fill_rects(&mut self, rects: &[Rect], color: Color) -> Result<(), String> {
+        if rects.is_empty() {
+            return Ok(());
+        }
+        match self {
+            RenderCanvas::Window(canvas) => fill_rects(canvas, rects, color),
+            RenderCanvas::Headless(canvas) => fill_rects(canvas, rects, color),
+        }
+    }
+
+    // draws the outline of a rect
+    pub(crate) fn draw_rect(&mut self, rect: Rect, color: Color) -> Result<(), String> {
+        match self {
+            RenderCanvas::Window(canvas) => draw_rect(canvas, rect, color),
+            RenderCanvas::Headless(canvas) => draw_rect(canvas, rect, color),
+        }
+    }
+
+    // clears `target` and copies the (source, destination) regions of every batch onto it, the
+    // n-th batch is copied from the n-th atlas
+    pub(crate) fn draw_to_texture(
+        &mut self,
+        target: &mut Texture,
+        atlases: &[Texture],
+        batches: &[Vec<(Rect, Rect)>],
+    ) -> Result<(), String> {
+        match self {
+            RenderCanvas::Window(canvas) => draw_to_texture(canvas, target, atlases, batches),
+            RenderCanvas::Headless(canvas) => draw_to_texture(canvas, target, atlases, batches),
+        }
+    }
+
+    pub(crate) fn render_world(
+        &mut self,
+        atlases: &mut [Texture],
+        world: &World,
+        camera: &Camera,
+    ) -> Result<(), String> {
+        match self {
+            RenderCanvas::Window(canvas) => render_world(canvas, atlases, world, camera),
+            RenderCanvas::Headless(canvas) => render_world(canvas, atlases, world, camera),
+        }
+    }
+}
+
+impl RenderTextureCreator {
+    pub(crate) fn create_texture_target(&self, width: u32, height: u32) -> Result<Texture, String> {
+        let mut texture = match self {
+            RenderTextureCreator::Window(texture_creator) => {
+                texture_creator.create_texture_target(PixelFormatEnum::RGBA8888, width, height)
+            }
+            RenderTextureCreator::Headless(texture_creator) => {
+                texture_creator.create_texture_target(PixelFormatEnum::RGBA8888, width, height)
+            }
+        }
+        .map_err(|e| e.to_string())?;
+
+        // the parts that are not drawn on have to stay transparent
+        texture.set_blend_mode(BlendMode::Blend);
+        Ok(texture)
+    }
+
+    // a RGBA32 texture whose pixels are written with Texture::update
+    pub(crate) fn create_texture_streaming(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, String> {
+        let mut texture = match self {
+            RenderTextureCreator::Window(texture_creator) => {
+                texture_creator.create_texture_streaming(PixelFormatEnum::RGBA32, width, height)
+            }
+            RenderTextureCreator::Headless(texture_creator) => {
+                texture_creator.create_texture_streaming(PixelFormatEnum::RGBA32, width, height)
+            }
+        }
+        .map_err(|e| e.to_string())?;
+
+        texture.set_blend_mode(BlendMode::Blend);
+        Ok(texture)
+    }
+
+    pub(crate) fn load_texture<P: AsRef<Path>>(&self, path: P) -> Result<Texture, String> {
+        match self {
+            RenderTextureCreator::Window(texture_creator) => texture_creator.load_texture(path),
+            RenderTextureCreator::Headless(texture_creator) => texture_creator.load_texture(path),
+        }
+    }
+
+    pub(crate) fn load_texture_bytes(&self, bytes: &[u8]) -> Result<Texture, String> {
+        match self {
+            RenderTextureCreator::Window(texture_creator) => {
+                texture_creator.load_texture_bytes(bytes)
+            }
+            RenderTextureCreator::Headless(texture_creator) => {
+                texture_creator.load_texture_bytes(bytes)
+            }
+        }
+    }
+}
+
+// textures are not freed automatically with the unsafe_textures feature of sdl2, each one has to
+// be destroyed by hand while the renderer that created it still exists
+pub(crate) fn destroy_texture(texture: Texture) {
+    unsafe { texture.destroy() };
+}
+
+fn render_world<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    atlases: &mut [Texture],
+    world: &World,
+    camera: &Camera,
+) -> Result<(), String> {
+    render_sprites(canvas, atlases, world.system_data(), camera)
+}
+
+fn fill_rects<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    rects: &[Rect],
+    color: Color,
+) -> Result<(), String> {
+    canvas.set_draw_color(color);
+    canvas.set_blend_mode(BlendMode::Blend);
+    let result = canvas.fill_rects(rects);
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    result
+}
+
+fn draw_rect<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    rect: Rect,
+    color: Color,
+) -> Result<(), String> {
+    canvas.set_draw_color(color);
+    let result = canvas.draw_rect(rect);
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    result
+}
+
+fn draw_to_texture<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    target: &mut Texture,
+    atlases: &[Texture],
+    batches: &[Vec<(Rect, Rect)>],
+) -> Result<(), String> {
+    let mut result = Ok(());
+    canvas
+        .with_texture_canvas(target, |texture_canvas| {
+            texture_canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+            texture_canvas.clear();
+            texture_canvas.set_draw_color(Color::RGB(0, 0, 0));
+
+            for (atlas, copies) in atlases.iter().zip(batches) {
+                for (src, dst) in copies {
+                    if let Err(e) = texture_canvas.copy(atlas, *src, *dst) {
+                        result = Err(e);
+                        return;
+                    }
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    result
+}
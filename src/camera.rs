@@ -1,9 +1,234 @@
-use sdl2::rect::Point;
+use std::ops::RangeInclusive;
 
-#[derive(Debug)]
+use sdl2::rect::{Point, Rect};
+
+use crate::TILE_SIZE;
+
+// a tile is never drawn smaller than one pixel
+pub const MIN_SCALE: f32 = 1.0 / TILE_SIZE as f32;
+pub const DEFAULT_MAX_SCALE: f32 = 8.0;
+// fraction of the remaining distance to the target scale covered every frame
+const ZOOM_EASING: f32 = 0.25;
+pub const DEFAULT_FOLLOW_DEAD_ZONE: (u32, u32) = (TILE_SIZE as u32 * 4, TILE_SIZE as u32 * 3);
+pub const DEFAULT_FOLLOW_EASING: f32 = 0.1;
+
+/// Transform between world coordinates (pixels of the unscaled map, a tile at `(col, row)` is
+/// centered on `(col * TILE_SIZE, row * TILE_SIZE)`) and screen coordinates.
+///
+/// `screen = (world + pan - pivot) * scale + pivot`, where the pivot is the centre of the
+/// viewport, so changing the scale keeps whatever is in the middle of the screen in place.
+#[derive(Debug, Clone)]
 pub struct Camera {
-    pub screen_offset: (i32, i32),
+    pub(crate) pan: (f32, f32),
+    pub(crate) scale: f32,
+    pub(crate) target_scale: f32,
+    // screen position that stays in place while easing towards the target scale
+    pub(crate) zoom_anchor: (f32, f32),
+    pub(crate) scale_bounds: (f32, f32),
+    pub(crate) viewport: (u32, u32),
     pub(crate) chase_robot: bool,
-    pub(crate) zoom_level: i32,
     pub(crate) robot_position: Point,
+    // area around the pivot, in screen pixels, in which the chased robot can move freely
+    pub(crate) follow_dead_zone: (u32, u32),
+    // fraction of the distance to the dead zone covered every frame
+    pub(crate) follow_easing: f32,
+}
+
+impl Camera {
+    pub fn new(viewport: (u32, u32)) -> Self {
+        Camera {
+            pan: (0.0, 0.0),
+            scale: 1.0,
+            target_scale: 1.0,
+            zoom_anchor: (viewport.0 as f32 / 2.0, viewport.1 as f32 / 2.0),
+            scale_bounds: (MIN_SCALE, DEFAULT_MAX_SCALE),
+            viewport,
+            chase_robot: false,
+            robot_position: Point::new(0, 0),
+            follow_dead_zone: DEFAULT_FOLLOW_DEAD_ZONE,
+            follow_easing: DEFAULT_FOLLOW_EASING,
+        }
+    }
+
+    pub fn pivot(&self) -> (f32, f32) {
+        (self.viewport.0 as f32 / 2.0, self.viewport.1 as f32 / 2.0)
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    // sets the scale right away, keeping the pivot in place
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(self.scale_bounds.0, self.scale_bounds.1);
+        self.target_scale = self.scale;
+    }
+    pub fn target_scale(&self) -> f32 {
+        self.target_scale
+    }
+    pub fn scale_bounds(&self) -> (f32, f32) {
+        self.scale_bounds
+    }
+    pub fn set_scale_bounds(&mut self, min: f32, max: f32) {
+        let min = min.max(MIN_SCALE);
+        self.scale_bounds = (min, max.max(min));
+        self.scale = self.scale.clamp(min, self.scale_bounds.1);
+        self.target_scale = self.target_scale.clamp(min, self.scale_bounds.1);
+    }
+
+    /// Starts zooming by `factor` around the given screen position, the world point under it
+    /// stays under it while [`Camera::update_zoom`] eases the scale towards the new value.
+    pub fn zoom_towards(&mut self, anchor: (f32, f32), factor: f32) {
+        self.target_scale =
+            (self.target_scale * factor).clamp(self.scale_bounds.0, self.scale_bounds.1);
+        self.zoom_anchor = anchor;
+    }
+    // moves the scale one frame closer to the target scale
+    pub fn update_zoom(&mut self) {
+        if self.scale == self.target_scale {
+            return;
+        }
+
+        let mut scale = self.scale + (self.target_scale - self.scale) * ZOOM_EASING;
+        if (self.target_scale - scale).abs() < self.target_scale * 1e-3 {
+            scale = self.target_scale;
+        }
+        self.scale_around(self.zoom_anchor, scale);
+    }
+    // changes the scale keeping the world point under `anchor` in place
+    fn scale_around(&mut self, anchor: (f32, f32), scale: f32) {
+        let world = self.screen_to_world(anchor);
+        let pivot = self.pivot();
+
+        self.scale = scale;
+        self.pan = (
+            (anchor.0 - pivot.0) / scale + pivot.0 - world.0,
+            (anchor.1 - pivot.1) / scale + pivot.1 - world.1,
+        );
+    }
+    pub fn pan(&self) -> (f32, f32) {
+        self.pan
+    }
+    // whether the camera follows the chased robot
+    pub fn is_chasing_robot(&self) -> bool {
+        self.chase_robot
+    }
+    pub fn set_pan(&mut self, pan: (f32, f32)) {
+        self.pan = pan;
+    }
+    // moves the map by a distance measured on the screen, e.g. a mouse drag
+    pub fn pan_by_screen(&mut self, dx: f32, dy: f32) {
+        self.pan.0 += dx / self.scale;
+        self.pan.1 += dy / self.scale;
+    }
+    // pans so that the given world position ends up under the pivot
+    pub fn center_on(&mut self, world: (f32, f32)) {
+        let pivot = self.pivot();
+        self.pan = (pivot.0 - world.0, pivot.1 - world.1);
+    }
+
+    pub fn set_follow(&mut self, dead_zone: (u32, u32), easing: f32) {
+        self.follow_dead_zone = dead_zone;
+        self.follow_easing = easing.clamp(0.0, 1.0);
+    }
+    /// Eases the pan so that the world position `target` gets back inside the dead zone, the
+    /// dead zone is measured on the screen so it keeps its size at every scale.
+    pub fn follow(&mut self, target: (f32, f32)) {
+        let (x, y) = self.world_to_screen(target);
+        let pivot = self.pivot();
+
+        let dx = outside(x - pivot.0, self.follow_dead_zone.0 as f32 / 2.0);
+        let dy = outside(y - pivot.1, self.follow_dead_zone.1 as f32 / 2.0);
+
+        self.pan.0 -= dx * self.follow_easing / self.scale;
+        self.pan.1 -= dy * self.follow_easing / self.scale;
+    }
+
+    pub fn world_to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let pivot = self.pivot();
+        (
+            (x + self.pan.0 - pivot.0) * self.scale + pivot.0,
+            (y + self.pan.1 - pivot.1) * self.scale + pivot.1,
+        )
+    }
+    pub fn screen_to_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let pivot = self.pivot();
+        (
+            (x - pivot.0) / self.scale + pivot.0 - self.pan.0,
+            (y - pivot.1) / self.scale + pivot.1 - self.pan.1,
+        )
+    }
+
+    /// Returns the `(col, row)` of the tile under the given screen position.
+    pub fn screen_to_map(&self, screen: (f32, f32)) -> (i32, i32) {
+        let (x, y) = self.screen_to_world(screen);
+        let half_tile = TILE_SIZE as f32 / 2.0;
+        (
+            ((x + half_tile) / TILE_SIZE as f32).floor() as i32,
+            ((y + half_tile) / TILE_SIZE as f32).floor() as i32,
+        )
+    }
+    /// Returns the screen position of the centre of the tile at `(col, row)`.
+    pub fn map_to_screen(&self, (col, row): (i32, i32)) -> (f32, f32) {
+        self.world_to_screen(((col * TILE_SIZE) as f32, (row * TILE_SIZE) as f32))
+    }
+
+    /// Returns the `(col, row)` of the first and last tile that are at least partially inside
+    /// the viewport.
+    pub fn visible_tiles(&self) -> ((i32, i32), (i32, i32)) {
+        let min = self.screen_to_map((0.0, 0.0));
+        let max = self.screen_to_map((self.viewport.0 as f32, self.viewport.1 as f32));
+        (min, max)
+    }
+    // the rows and cols of a `rows` x `cols` map that are on screen, none when the map is not
+    pub(crate) fn visible_ranges(
+        &self,
+        rows: usize,
+        cols: usize,
+    ) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        let ((min_col, min_row), (max_col, max_row)) = self.visible_tiles();
+        let (rows, cols) = (rows as i32, cols as i32);
+        if max_col < 0 || max_row < 0 || min_col >= cols || min_row >= rows {
+            return None;
+        }
+        Some((
+            min_row.max(0) as usize..=max_row.min(rows - 1) as usize,
+            min_col.max(0) as usize..=max_col.min(cols - 1) as usize,
+        ))
+    }
+    pub(crate) fn viewport_rect(&self) -> Rect {
+        Rect::new(0, 0, self.viewport.0, self.viewport.1)
+    }
+
+    pub(crate) fn world_point_to_screen(&self, world: Point) -> Point {
+        let (x, y) = self.world_to_screen((world.x() as f32, world.y() as f32));
+        Point::new(x.round() as i32, y.round() as i32)
+    }
+    // the edges are transformed separately so that neighbouring tiles never leave gaps
+    pub(crate) fn world_rect_to_screen(&self, center: Point, width: u32, height: u32) -> Rect {
+        let (left, top) = self.world_to_screen((
+            center.x() as f32 - width as f32 / 2.0,
+            center.y() as f32 - height as f32 / 2.0,
+        ));
+        let (right, bottom) = self.world_to_screen((
+            center.x() as f32 + width as f32 / 2.0,
+            center.y() as f32 + height as f32 / 2.0,
+        ));
+        let (left, top) = (left.round() as i32, top.round() as i32);
+        Rect::new(
+            left,
+            top,
+            (right.round() as i32 - left).max(1) as u32,
+            (bottom.round() as i32 - top).max(1) as u32,
+        )
+    }
+}
+
+// how far `distance` reaches past `half_size` on either side, 0 if it is inside
+fn outside(distance: f32, half_size: f32) -> f32 {
+    if distance > half_size {
+        distance - half_size
+    } else if distance < -half_size {
+        distance + half_size
+    } else {
+        0.0
+    }
 }
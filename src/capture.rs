@@ -0,0 +1,70 @@
+use sdl2::image::SaveSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Canvas, RenderTarget};
+use sdl2::surface::Surface;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// writes every `every`-th frame drawn by tick() into `directory` as a numbered png
+#[derive(Debug)]
+pub(crate) struct FrameRecorder {
+    directory: PathBuf,
+    every: u32,
+    frame: u32,
+}
+
+impl FrameRecorder {
+    pub(crate) fn new(directory: PathBuf, every: u32) -> Result<Self, String> {
+        if every == 0 {
+            return Err("frames have to be recorded at least every 1 frame".to_string());
+        }
+        fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+
+        Ok(FrameRecorder {
+            directory,
+            every,
+            frame: 0,
+        })
+    }
+
+    // advances the frame counter, returns the file the current frame has to be written to
+    pub(crate) fn next_frame(&mut self) -> Option<PathBuf> {
+        let frame = self.frame;
+        self.frame += 1;
+
+        // the saved frames are numbered consecutively, whatever the interval
+        if frame.is_multiple_of(self.every) {
+            Some(
+                self.directory
+                    .join(format!("frame_{:06}.png", frame / self.every)),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn read_canvas<T: RenderTarget>(
+    canvas: &Canvas<T>,
+) -> Result<(u32, u32, Vec<u8>), String> {
+    let (width, height) = canvas.output_size()?;
+    let pixels = canvas.read_pixels(None, PixelFormatEnum::RGBA32)?;
+    Ok((width, height, pixels))
+}
+
+pub(crate) fn save_png(
+    width: u32,
+    height: u32,
+    mut pixels: Vec<u8>,
+    path: &Path,
+) -> Result<(), String> {
+    let surface = Surface::from_data(
+        &mut pixels,
+        width,
+        height,
+        width * PixelFormatEnum::RGBA32.byte_size_per_pixel() as u32,
+        PixelFormatEnum::RGBA32,
+    )?;
+    surface.save(path)
+}
@@ -0,0 +1,291 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use robotics_lib::world::tile::Tile;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::Texture;
+
+use crate::backend::{destroy_texture, RenderCanvas, RenderTextureCreator};
+use crate::camera::Camera;
+use crate::texture_manager::{SpriteKey, SpriteTable, TextureType};
+use crate::TILE_SIZE;
+
+// side of a chunk, in tiles
+pub(crate) const CHUNK_SIZE: usize = 32;
+const CHUNK_PIXELS: u32 = CHUNK_SIZE as u32 * TILE_SIZE as u32;
+// chunks that have not been on screen for this many frames are freed
+const UNSEEN_FRAMES: u64 = 120;
+
+struct Chunk {
+    texture: Texture,
+    // side of the texture, smaller than CHUNK_PIXELS when it has been drawn zoomed out
+    pixels: u32,
+    dirty: bool,
+    // the animated sprites of its tiles, it is redrawn whenever one of them changes frame
+    animated: Vec<SpriteKey>,
+    // frame the chunk has last been on screen in
+    seen_at: u64,
+}
+
+// the Tiles layer pre-rendered in square chunks, a chunk is redrawn only after one of its tiles
+// changed, so drawing the whole map costs one copy per visible chunk
+//
+// only the chunks seen in the last frames are kept, and they are drawn at a lower resolution
+// while zoomed out, so a large map costs about as much memory as the screen
+pub(crate) struct ChunkCache {
+    chunks: HashMap<(usize, usize), Chunk>,
+    // animation time the animated chunks have last been drawn at
+    drawn_at: Duration,
+    frame: u64,
+}
+
+impl ChunkCache {
+    pub(crate) fn new() -> Self {
+        ChunkCache {
+            chunks: HashMap::new(),
+            drawn_at: Duration::ZERO,
+            frame: 0,
+        }
+    }
+
+    // marks the chunk containing the tile at (row, col) to be redrawn
+    pub(crate) fn invalidate(&mut self, row: usize, col: usize) {
+        if let Some(chunk) = self.chunks.get_mut(&(row / CHUNK_SIZE, col / CHUNK_SIZE)) {
+            chunk.dirty = true;
+        }
+    }
+
+    // redraws every chunk, e.g. after the atlas changed
+    pub(crate) fn invalidate_all(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.dirty = true;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn render(
+        &mut self,
+        canvas: &mut RenderCanvas,
+        texture_creator: &RenderTextureCreator,
+        atlases: &[Texture],
+        tiles_world: &[Vec<Option<Tile>>],
+        sprite_table: &SpriteTable,
+        camera: &Camera,
+        time: Duration,
+    ) -> Result<(), String> {
+        let changed = sprite_table.changed_tile_frames(self.drawn_at, time);
+        if !changed.is_empty() {
+            for chunk in self.chunks.values_mut() {
+                if chunk.animated.iter().any(|key| changed.contains(key)) {
+                    chunk.dirty = true;
+                }
+            }
+        }
+        self.drawn_at = time;
+
+        self.frame += 1;
+        let frame = self.frame;
+
+        let rows = tiles_world.len();
+        let cols = tiles_world.iter().map(|r| r.len()).max().unwrap_or(0);
+        let (map_chunk_rows, map_chunk_cols) =
+            (rows.div_ceil(CHUNK_SIZE), cols.div_ceil(CHUNK_SIZE));
+        self.evict(|&(row, col), chunk| {
+            row >= map_chunk_rows || col >= map_chunk_cols || frame - chunk.seen_at > UNSEEN_FRAMES
+        });
+
+        let Some((rows_on_screen, cols_on_screen)) = camera.visible_ranges(rows, cols) else {
+            return Ok(());
+        };
+        let chunk_rows = rows_on_screen.start() / CHUNK_SIZE..=rows_on_screen.end() / CHUNK_SIZE;
+        let chunk_cols = cols_on_screen.start() / CHUNK_SIZE..=cols_on_screen.end() / CHUNK_SIZE;
+        let pixels = chunk_pixels(camera.scale());
+
+        for chunk_row in chunk_rows {
+            for chunk_col in chunk_cols.clone() {
+                let key = (chunk_row, chunk_col);
+                // a chunk drawn at another resolution is created again
+                if self
+                    .chunks
+                    .get(&key)
+                    .is_some_and(|chunk| chunk.pixels != pixels)
+                {
+                    self.evict(|other, _| *other == key);
+                }
+                let chunk = match self.chunks.entry(key) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(Chunk {
+                        texture: texture_creator.create_texture_target(pixels, pixels)?,
+                        pixels,
+                        dirty: true,
+                        animated: Vec::new(),
+                        seen_at: frame,
+                    }),
+                };
+                chunk.seen_at = frame;
+                if chunk.dirty {
+                    let (batches, animated) =
+                        chunk_copies(tiles_world, sprite_table, atlases.len(), key, pixels, time);
+                    canvas.draw_to_texture(&mut chunk.texture, atlases, &batches)?;
+                    chunk.animated = animated;
+                    chunk.dirty = false;
+                }
+
+                // the top left corner of a chunk is the top left corner of its first tile
+                let center = Point::new(
+                    (chunk_col * CHUNK_SIZE) as i32 * TILE_SIZE - TILE_SIZE / 2
+                        + CHUNK_PIXELS as i32 / 2,
+                    (chunk_row * CHUNK_SIZE) as i32 * TILE_SIZE - TILE_SIZE / 2
+                        + CHUNK_PIXELS as i32 / 2,
+                );
+                canvas.copy(
+                    &chunk.texture,
+                    camera.world_rect_to_screen(center, CHUNK_PIXELS, CHUNK_PIXELS),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // frees every chunk
+    pub(crate) fn clear(&mut self) {
+        for (_, chunk) in self.chunks.drain() {
+            destroy_texture(chunk.texture);
+        }
+    }
+
+    // frees the chunks `evicted` returns true for
+    fn evict(&mut self, evicted: impl Fn(&(usize, usize), &Chunk) -> bool) {
+        let keys = self
+            .chunks
+            .iter()
+            .filter(|(key, chunk)| evicted(key, chunk))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            if let Some(chunk) = self.chunks.remove(&key) {
+                destroy_texture(chunk.texture);
+            }
+        }
+    }
+}
+
+impl Drop for ChunkCache {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// the (atlas region, chunk region) pairs of every known tile inside a chunk, grouped by atlas,
+// and the animated sprites among them
+fn chunk_copies(
+    tiles_world: &[Vec<Option<Tile>>],
+    sprite_table: &SpriteTable,
+    atlas_count: usize,
+    (chunk_row, chunk_col): (usize, usize),
+    pixels: u32,
+    time: Duration,
+) -> (Vec<Vec<(Rect, Rect)>>, Vec<SpriteKey>) {
+    let mut batches = vec![Vec::new(); atlas_count];
+    let mut animated = Vec::new();
+
+    let rows = tiles_world
+        .iter()
+        .enumerate()
+        .skip(chunk_row * CHUNK_SIZE)
+        .take(CHUNK_SIZE);
+    for (row, tiles) in rows {
+        let cols = tiles
+            .iter()
+            .enumerate()
+            .skip(chunk_col * CHUNK_SIZE)
+            .take(CHUNK_SIZE);
+
+        for (col, tile) in cols {
+            let Some(tile) = tile else {
+                continue;
+            };
+            let texture_type = TextureType::Tile(tile.tile_type);
+            if let Some(key) = texture_type.sprite_key() {
+                if sprite_table.texture_animation(&texture_type).is_some()
+                    && !animated.contains(&key)
+                {
+                    animated.push(key);
+                }
+            }
+
+            let Some(region) = sprite_table.region_at(&texture_type, time) else {
+                continue;
+            };
+            let Some(copies) = batches.get_mut(region.atlas) else {
+                continue;
+            };
+
+            let rect = region.rect;
+            let center = Point::new(
+                (col % CHUNK_SIZE) as i32 * TILE_SIZE + TILE_SIZE / 2,
+                (row % CHUNK_SIZE) as i32 * TILE_SIZE + TILE_SIZE / 2,
+            );
+            let dst = Rect::from_center(center, rect.width(), rect.height());
+            copies.push((rect, scale_rect(dst, pixels)));
+        }
+    }
+    (batches, animated)
+}
+
+// side of the chunk textures at a camera scale, halved for as long as a texel still covers at
+// least a pixel of the screen
+fn chunk_pixels(scale: f32) -> u32 {
+    let mut pixels = CHUNK_PIXELS;
+    while pixels > 1 && (pixels / 2) as f32 >= CHUNK_PIXELS as f32 * scale {
+        pixels /= 2;
+    }
+    pixels
+}
+
+// a rect of a full resolution chunk on a chunk texture with a side of `pixels`
+fn scale_rect(rect: Rect, pixels: u32) -> Rect {
+    let scale = |v: i32| (v * pixels as i32).div_euclid(CHUNK_PIXELS as i32);
+    let (left, top) = (scale(rect.left()), scale(rect.top()));
+    Rect::new(
+        left,
+        top,
+        (scale(rect.right()) - left).max(1) as u32,
+        (scale(rect.bottom()) - top).max(1) as u32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::MIN_SCALE;
+
+    #[test]
+    fn zoomed_out_chunks_are_smaller() {
+        assert_eq!(chunk_pixels(2.0), CHUNK_PIXELS);
+        assert_eq!(chunk_pixels(1.0), CHUNK_PIXELS);
+        assert_eq!(chunk_pixels(0.6), CHUNK_PIXELS);
+        assert_eq!(chunk_pixels(0.5), CHUNK_PIXELS / 2);
+        // a tile is a single texel at the smallest scale
+        assert_eq!(chunk_pixels(MIN_SCALE), CHUNK_SIZE as u32);
+    }
+
+    #[test]
+    fn rects_keep_their_place_on_smaller_chunks() {
+        let tile = Rect::new(TILE_SIZE * 3, TILE_SIZE, TILE_SIZE as u32, TILE_SIZE as u32);
+        assert_eq!(scale_rect(tile, CHUNK_PIXELS), tile);
+        assert_eq!(
+            scale_rect(tile, CHUNK_PIXELS / 2),
+            Rect::new(
+                TILE_SIZE * 3 / 2,
+                TILE_SIZE / 2,
+                TILE_SIZE as u32 / 2,
+                TILE_SIZE as u32 / 2
+            )
+        );
+        assert_eq!(scale_rect(tile, CHUNK_SIZE as u32), Rect::new(3, 1, 1, 1));
+    }
+}
@@ -1,7 +1,11 @@
+use std::time::Duration;
+
+use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use specs::{Component, VecStorage};
+use specs::{Component, Entity, VecStorage};
 
-use crate::texture_manager::TextureType;
+use crate::animation::Animation;
+use crate::texture_manager::{AtlasId, SpriteTable, TextureType};
 
 #[derive(Debug)]
 pub struct Position(pub(crate) Point);
@@ -13,9 +17,53 @@ impl Component for Position {
 #[derive(Debug)]
 pub struct Sprite {
     pub(crate) region: Rect,
+    pub(crate) atlas: AtlasId,
     pub(crate) texture_type: TextureType,
 }
 
+impl Sprite {
+    // panics if the sprite table has no region for the texture type
+    pub(crate) fn new(sprite_table: &SpriteTable, texture_type: TextureType) -> Self {
+        let region = sprite_table.region(&texture_type).unwrap();
+        Sprite {
+            region: region.rect,
+            atlas: region.atlas,
+            texture_type,
+        }
+    }
+}
+
 impl Component for Sprite {
     type Storage = VecStorage<Self>;
 }
+
+// replaces the region of the entity's Sprite with the current frame of an animation
+#[derive(Debug)]
+pub struct Animated {
+    pub(crate) animation: Animation,
+    // animation clock time at which the first frame was shown
+    pub(crate) start: Duration,
+}
+
+impl Component for Animated {
+    type Storage = VecStorage<Self>;
+}
+
+// multiplies the colors of the entity's Sprite
+#[derive(Debug)]
+pub struct Tint(pub(crate) Color);
+
+impl Component for Tint {
+    type Storage = VecStorage<Self>;
+}
+
+// keeps the entity at a fixed offset from another one, e.g. the name label of a robot
+#[derive(Debug)]
+pub struct Attached {
+    pub(crate) parent: Entity,
+    pub(crate) offset: Point,
+}
+
+impl Component for Attached {
+    type Storage = VecStorage<Self>;
+}
@@ -1,3 +1,4 @@
 //pub mod ambient_components;
 pub mod drawable_components;
 pub mod movement_components;
+pub mod robot_components;
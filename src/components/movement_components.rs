@@ -3,7 +3,6 @@ use specs::{Component, VecStorage};
 
 #[derive(Debug)]
 pub struct Velocity {
-    pub(crate) speed: i32,
     pub(crate) direction: Option<Direction>,
 }
 
@@ -0,0 +1,26 @@
+use robotics_lib::interface::Direction;
+use sdl2::rect::Point;
+use specs::{Component, VecStorage};
+
+use crate::texture_manager::SpriteRegion;
+
+/// Identifies one of the robots added with `MainState::add_robot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RobotId(pub(crate) usize);
+
+#[derive(Debug)]
+pub struct Robot {
+    pub(crate) id: RobotId,
+    // position of the tile given to the last update_robot, where the robot really is
+    pub(crate) target: Point,
+    // direction the robot is facing while it moves towards the target
+    pub(crate) direction: Option<Direction>,
+    // steps left before the robot reaches that tile
+    pub(crate) remaining_steps: u32,
+    // drawn instead of the robot sprites of the sprite table
+    pub(crate) skin: Option<SpriteRegion>,
+}
+
+impl Component for Robot {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,199 @@
+use robotics_lib::world::tile::Tile;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use crate::backend::RenderCanvas;
+use crate::camera::Camera;
+use crate::TILE_SIZE;
+
+// covers the tiles that have never been seen
+const UNSEEN_COLOR: Color = Color::RGB(24, 26, 34);
+// dims the tiles that have been seen, but that no robot sees right now
+const REMEMBERED_COLOR: Color = Color::RGBA(0, 0, 0, 150);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Visibility {
+    Unseen,
+    Remembered,
+    Visible,
+}
+
+// fog drawn over the map to tell apart the tiles that have never been seen, the ones that are
+// remembered with what they looked like last time and the ones the robots see right now
+#[derive(Debug, Clone)]
+pub(crate) struct FogOfWar {
+    pub(crate) enabled: bool,
+    // how many tiles around itself a robot sees, None if every known tile is in sight
+    pub(crate) radius: Option<usize>,
+}
+
+impl FogOfWar {
+    pub(crate) fn new() -> Self {
+        FogOfWar {
+            enabled: false,
+            radius: None,
+        }
+    }
+
+    // draws the fog over the visible part of the map, `robots` are the (row, col) of the robots
+    pub(crate) fn render(
+        &self,
+        canvas: &mut RenderCanvas,
+        tiles_world: &[Vec<Option<Tile>>],
+        robots: &[(usize, usize)],
+        camera: &Camera,
+    ) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let rows = tiles_world.len();
+        let cols = tiles_world.iter().map(|r| r.len()).max().unwrap_or(0);
+        let Some((rows_on_screen, cols_on_screen)) = camera.visible_ranges(rows, cols) else {
+            return Ok(());
+        };
+        let (min_col, max_col) = (*cols_on_screen.start(), *cols_on_screen.end());
+
+        let mut unseen = Vec::new();
+        let mut remembered = Vec::new();
+        // neighbouring tiles with the same visibility are covered by a single rect
+        for row in rows_on_screen {
+            let mut col = min_col;
+            while col <= max_col {
+                let visibility = self.visibility(tiles_world, robots, row, col);
+                let start = col;
+                while col < max_col
+                    && self.visibility(tiles_world, robots, row, col + 1) == visibility
+                {
+                    col += 1;
+                }
+
+                let run = run_rect(camera, row, start, col);
+                match visibility {
+                    Visibility::Unseen => unseen.push(run),
+                    Visibility::Remembered => remembered.push(run),
+                    Visibility::Visible => {}
+                }
+                col += 1;
+            }
+        }
+
+        canvas.fill_rects(&unseen, UNSEEN_COLOR)?;
+        canvas.fill_rects(&remembered, REMEMBERED_COLOR)
+    }
+
+    fn visibility(
+        &self,
+        tiles_world: &[Vec<Option<Tile>>],
+        robots: &[(usize, usize)],
+        row: usize,
+        col: usize,
+    ) -> Visibility {
+        let known = tiles_world
+            .get(row)
+            .and_then(|r| r.get(col))
+            .is_some_and(Option::is_some);
+        if !known {
+            return Visibility::Unseen;
+        }
+
+        let Some(radius) = self.radius else {
+            return Visibility::Visible;
+        };
+        let in_sight = robots
+            .iter()
+            .any(|(r, c)| r.abs_diff(row).max(c.abs_diff(col)) <= radius);
+        if in_sight {
+            Visibility::Visible
+        } else {
+            Visibility::Remembered
+        }
+    }
+}
+
+// the screen rect covering the tiles from `first_col` to `last_col` of a row
+fn run_rect(camera: &Camera, row: usize, first_col: usize, last_col: usize) -> Rect {
+    let center = Point::new(
+        (first_col + last_col) as i32 * TILE_SIZE / 2,
+        row as i32 * TILE_SIZE,
+    );
+    let width = (last_col - first_col + 1) as u32 * TILE_SIZE as u32;
+    camera.world_rect_to_screen(center, width, TILE_SIZE as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::grass_map;
+
+    // a 7x7 map where only the tile at (0, 6) is unknown
+    fn map() -> Vec<Vec<Option<Tile>>> {
+        let mut map = grass_map(7, 7);
+        map[0][6] = None;
+        map
+    }
+
+    fn fog_with(radius: Option<usize>) -> FogOfWar {
+        FogOfWar {
+            enabled: true,
+            radius,
+        }
+    }
+
+    #[test]
+    fn unknown_tiles_are_unseen() {
+        let map = map();
+        for radius in [None, Some(0), Some(10)] {
+            let fog = fog_with(radius);
+            assert_eq!(fog.visibility(&map, &[(0, 5)], 0, 6), Visibility::Unseen);
+            // and so is everything outside the map
+            assert_eq!(fog.visibility(&map, &[(0, 5)], 7, 0), Visibility::Unseen);
+            assert_eq!(fog.visibility(&map, &[(0, 5)], 0, 7), Visibility::Unseen);
+        }
+    }
+
+    #[test]
+    fn known_tiles_are_visible_without_a_radius() {
+        let map = map();
+        let fog = fog_with(None);
+        assert_eq!(fog.visibility(&map, &[], 6, 6), Visibility::Visible);
+        assert_eq!(fog.visibility(&map, &[(0, 0)], 6, 0), Visibility::Visible);
+    }
+
+    #[test]
+    fn robots_see_a_square_around_themselves() {
+        let map = map();
+        let fog = fog_with(Some(2));
+        let robots = [(3, 3)];
+        // the first row holds the unknown tile
+        for row in 1..7_usize {
+            for col in 0..7_usize {
+                // diagonals count as one tile, like steps to the side
+                let in_sight = row.abs_diff(3) <= 2 && col.abs_diff(3) <= 2;
+                let expected = if in_sight {
+                    Visibility::Visible
+                } else {
+                    Visibility::Remembered
+                };
+                assert_eq!(
+                    fog.visibility(&map, &robots, row, col),
+                    expected,
+                    "({}, {})",
+                    row,
+                    col
+                );
+            }
+        }
+        assert_eq!(fog.visibility(&map, &robots, 5, 5), Visibility::Visible);
+        assert_eq!(fog.visibility(&map, &robots, 6, 5), Visibility::Remembered);
+
+        // any robot is enough, and a radius of 0 only shows the tile the robot is on
+        let fog = fog_with(Some(0));
+        let robots = [(0, 0), (6, 6)];
+        assert_eq!(fog.visibility(&map, &robots, 6, 6), Visibility::Visible);
+        assert_eq!(fog.visibility(&map, &robots, 0, 0), Visibility::Visible);
+        assert_eq!(fog.visibility(&map, &robots, 0, 1), Visibility::Remembered);
+        // without robots every known tile is only remembered
+        assert_eq!(fog.visibility(&map, &[], 0, 0), Visibility::Remembered);
+    }
+}
@@ -0,0 +1,74 @@
+use robotics_lib::world::tile::Tile;
+use sdl2::pixels::Color;
+
+use crate::{MainState, WIDTH};
+
+use super::{scene::Scene, square::Square, text::Text};
+
+pub(crate) const INSPECTOR_SCENE: &str = "inspector";
+const PANEL_WIDTH: u32 = 320;
+const LINE_HEIGHT: i32 = 28;
+// distance of the panel from the top right corner of the screen
+const MARGIN: i32 = 20;
+
+// the side panel describing the tile at (row, col), `pinned` if it has been clicked instead of
+// being under the cursor
+pub(crate) fn draw_inspector(state: &mut MainState, (row, col): (usize, usize), pinned: bool) {
+    let tile = state
+        .tiles_world
+        .get(row)
+        .and_then(|r| r.get(col))
+        .cloned()
+        .flatten();
+
+    let mut lines = vec![format!(
+        "x: {}, y: {}{}",
+        col,
+        row,
+        if pinned { " (pinned)" } else { "" }
+    )];
+    lines.extend(describe(tile.as_ref()));
+
+    let height = (lines.len() as i32 * LINE_HEIGHT + MARGIN) as u32;
+    let left = WIDTH as i32 - MARGIN - PANEL_WIDTH as i32;
+
+    let mut inspector = Scene::new(INSPECTOR_SCENE.to_string(), 8);
+    inspector.add_element(Box::new(Square::new(
+        (left + PANEL_WIDTH as i32 / 2, MARGIN + height as i32 / 2),
+        (PANEL_WIDTH, height),
+        true,
+        false,
+        Color::RGBA(30, 30, 40, 200),
+        1,
+    )));
+    for (i, line) in lines.into_iter().enumerate() {
+        inspector.add_element(Box::new(Text::new(
+            line,
+            (left + MARGIN, MARGIN * 2 + i as i32 * LINE_HEIGHT),
+            0.5,
+            true,
+            2,
+        )));
+    }
+    inspector.draw(state);
+}
+
+fn describe(tile: Option<&Tile>) -> Vec<String> {
+    let Some(tile) = tile else {
+        return vec!["Unknown".to_string()];
+    };
+    vec![
+        format!("Type: {:?}", tile.tile_type),
+        // the debug output includes the quantity, or the range of a container
+        format!("Content: {:?}", tile.content),
+        format!("Elevation: {}", tile.elevation),
+        format!(
+            "Walkable: {}",
+            if tile.tile_type.properties().walk() {
+                "yes"
+            } else {
+                "no"
+            }
+        ),
+    ]
+}
@@ -1,10 +1,7 @@
 use robotics_lib::world::tile::Content;
 use sdl2::pixels::Color;
 
-use crate::{
-    texture_manager::{get_texture_type_from_content, TextureType},
-    MainState, HEIGHT, WIDTH,
-};
+use crate::{texture_manager::TextureType, MainState, HEIGHT, WIDTH};
 
 use super::{item::Item, scene::Scene, square::Square, text::Text};
 
@@ -58,14 +55,14 @@ impl Menu {
                         (x + 50, y),
                         1.7,
                         true,
-                        get_texture_type_from_content(item.0.clone()),
+                        TextureType::Content(item.0.clone()),
                         2,
                     )));
 
                     invetory_scene.add_element(Box::new(Text::new(
                         format!(
                             "{:?} x{}",
-                            get_texture_type_from_content(item.0.clone()).to_string(),
+                            TextureType::Content(item.0.clone()).to_string(),
                             item.1
                         ),
                         (x, y + 50),
@@ -107,12 +104,18 @@ impl Menu {
                 let mut x = 100;
                 let mut markers = state.markers.get_all().clone();
                 for marker in markers {
-                    let marked_tile =
-                        state.tiles_world[marker.0 .0 as usize][marker.0 .1 as usize].clone();
+                    // markers can be placed before the map is known or outside of it, a
+                    // negative coordinate wraps around to a row or col that does not exist
+                    let marked_tile = state
+                        .tiles_world
+                        .get(marker.0 .0 as usize)
+                        .and_then(|tiles| tiles.get(marker.0 .1 as usize))
+                        .cloned()
+                        .flatten();
                     match marked_tile {
                         Some(tile) => {
                             markers_menu.add_element(Box::new(Text::new(
-                                format!("{:?}", marker.0),
+                                format!("{:?} {}", marker.0, marker.1.label()),
                                 (x, y),
                                 0.7,
                                 true,
@@ -129,7 +132,7 @@ impl Menu {
                                 (x + 130, y),
                                 0.7,
                                 true,
-                                get_texture_type_from_content(tile.content.clone()),
+                                TextureType::Content(tile.content.clone()),
                                 2,
                             )));
                             markers_menu.add_element(Box::new(Text::new(
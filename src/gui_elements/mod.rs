@@ -1,4 +1,5 @@
 pub(crate) mod draw;
+pub(crate) mod inspector;
 pub(crate) mod item;
 pub(crate) mod menus;
 pub(crate) mod scene;
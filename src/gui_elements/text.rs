@@ -47,24 +47,28 @@ impl Drawable for Text {
     fn draw(&self, state: &mut MainState) {
         let mut x = self.get_position().0;
         for c in self.text.chars() {
-            state.scenes.push((
-                self.get_parent().unwrap().0.clone(),
-                self.get_parent().unwrap().1,
-                self.get_layer(),
-            ));
-
-            MainState::add_ui_element(
-                &mut state.ui_elements,
-                &state.sprite_table,
-                Layer::Ui(
-                    self.get_parent().unwrap().0,
+            let texture_type = TextureType::FontCharater(c, self.scale, self.fixed);
+            // characters missing from the font are left out, like in the robot names
+            if state.sprite_table.region(&texture_type).is_some() {
+                state.scenes.push((
+                    self.get_parent().unwrap().0.clone(),
                     self.get_parent().unwrap().1,
                     self.get_layer(),
-                ),
-                TextureType::FontCharater(c, self.scale, self.fixed),
-                x,
-                self.get_position().1,
-            );
+                ));
+
+                MainState::add_ui_element(
+                    &mut state.ui_elements,
+                    &state.sprite_table,
+                    Layer::Ui(
+                        self.get_parent().unwrap().0,
+                        self.get_parent().unwrap().1,
+                        self.get_layer(),
+                    ),
+                    texture_type,
+                    x,
+                    self.get_position().1,
+                );
+            }
             x += (TILE_SIZE as f32 * 0.3 + (TILE_SIZE as f32 * 0.3 * self.scale - 1.0)) as i32;
         }
     }
@@ -1,60 +1,107 @@
-use components::drawable_components::{Position, Sprite};
+use components::drawable_components::{Animated, Attached, Position, Sprite, Tint};
 use components::movement_components::Velocity;
+use components::robot_components::Robot;
+use gui_elements::inspector::{draw_inspector, INSPECTOR_SCENE};
 use gui_elements::menus::{Menu, MenuTypes};
 use gui_elements::scene::Scene;
 use gui_elements::square::Square;
 use gui_elements::text::Text;
 
-use markers::Markers;
-use renderer::{calculate_map_coords, render_sprites, Layer, RENDER_ORDER};
+use backend::{destroy_texture, RenderCanvas, RenderTextureCreator};
+use markers::{Markers, DEFAULT_CATEGORY, DEFAULT_MARKER_COLOR};
+use renderer::{Layer, RENDER_ORDER};
 use robotics_lib::interface::Direction;
 use robotics_lib::world::environmental_conditions::{DayTime, WeatherType};
-use robotics_lib::world::tile::{Content, Tile};
-use sdl2::pixels::Color;
-use sdl2::render::{Canvas, TextureCreator};
-use sdl2::video::{Window, WindowContext};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::surface::Surface;
 use sdl2::Sdl;
-use systems::movement_systems::{ChangeDirectionSystem, MoveSystem};
+use systems::animation_systems::{
+    AnimationClock, AnimationSystem, RobotSpriteSystem, RobotSprites,
+};
+use systems::camera_systems::{CameraFollowSystem, ChasedRobot};
+use systems::movement_systems::{AttachSystem, ChangeDirectionSystem, MoveSystem};
 
 use sdl2::event::Event;
-use sdl2::image::{InitFlag, LoadTexture};
+use sdl2::image::InitFlag;
 use sdl2::keyboard::Keycode;
 use sdl2::rect::{Point, Rect};
-use specs::{Builder, Dispatcher, DispatcherBuilder, World, WorldExt};
+use sdl2::render::Texture;
+use sdl2::EventPump;
+use specs::{Builder, Dispatcher, DispatcherBuilder, Entity, Join, RunNow, World, WorldExt};
 
-use texture_manager::{get_texture_type_from_content, SpriteTable};
+use texture_manager::{AtlasId, SpriteRegion, SpriteTable, DEFAULT_ATLAS_ID};
 
+use assets::{FileWatcher, ATLAS_FILE, DEFAULT_ATLAS};
 use camera::Camera;
+use capture::{save_png, FrameRecorder};
+use chunks::ChunkCache;
+use fog::FogOfWar;
+use minimap::Minimap;
+use session::{CameraState, MarkerState, Session};
 
-use std::collections::HashMap;
-use std::path::Path;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crate::markers::Marker;
 use crate::texture_manager::{OverlayType, TextureType};
+use crate::trails::Trails;
 
 mod animation;
-mod camera;
+mod assets;
+mod backend;
+pub mod camera;
+mod capture;
+mod chunks;
 mod components;
+mod fog;
 pub mod gui_elements;
 mod markers;
+mod minimap;
 mod renderer;
+mod session;
 mod systems;
+#[cfg(test)]
+mod test_utils;
 pub mod texture_manager;
+mod trails;
+
+pub use components::robot_components::RobotId;
 
 const WIDTH: u32 = 1280;
 const HEIGHT: u32 = 720;
 
 pub const TILE_SIZE: i32 = 32;
+// scale factor applied for every notch of the mouse wheel
+const ZOOM_STEP: f32 = 1.1;
+// a slow frame catches up on at most this many simulation steps
+const MAX_STEPS_PER_FRAME: u32 = 5;
 //const ROBOT_SPEED: i32 = 6;
+/// Robots that jump farther than this many tiles are not drawn moving there.
+pub const SNAP_DISTANCE: i32 = 4;
+// color of the trail of robots without a tint
+const TRAIL_COLOR: Color = Color::RGB(255, 220, 0);
+// side, in screen pixels, of the dots that make up a trail
+const TRAIL_DOT_SIZE: u32 = 8;
+// side, in screen pixels, of the dot drawn for a path that stays on a single tile
+const PATH_DOT_SIZE: u32 = 12;
+// size of the characters of the robot names
+const LABEL_SCALE: f32 = 0.5;
+
+// tile and content entity drawn on every known cell, indexed by (row, col)
+type CellEntities = HashMap<(usize, usize), (Option<Entity>, Option<Entity>)>;
 
 pub struct MainState<'window> {
-    sdl_context: Sdl,
-    canvas: Canvas<Window>,
-    texture_creator: TextureCreator<WindowContext>,
+    // never read, it keeps sdl initialized for as long as the state exists
+    _sdl_context: Sdl,
+    canvas: RenderCanvas,
+    texture_creator: RenderTextureCreator,
     worlds: HashMap<Layer, World>,
     ui_elements: HashMap<(String, u32, u32), World>,
     tiles_world: Vec<Vec<Option<Tile>>>,
+    cell_entities: CellEntities,
+    chunks: ChunkCache,
     energy: u32,
     backpack: Vec<(Content, u32)>,
     dispatcher: Dispatcher<'window, 'window>,
@@ -65,6 +112,38 @@ pub struct MainState<'window> {
     framerate: u32,
     scenes: Vec<(String, u32, u32)>,
     menus: Vec<Menu>,
+    recorder: Option<FrameRecorder>,
+    cursor: Point,
+    event_pump: EventPump,
+    // the textures sprites are drawn from, indexed by AtlasId
+    atlases: Vec<Texture>,
+    // files the atlases have been loaded from, None for the one embedded in the crate
+    atlas_paths: Vec<Option<PathBuf>>,
+    hot_reload: bool,
+    atlas_watchers: Vec<(AtlasId, FileWatcher)>,
+    // why the last reload of an atlas failed, until it is taken with take_reload_error
+    reload_error: Option<String>,
+    // time that has not been simulated yet, consumed in fixed steps of 1 / framerate
+    accumulator: Duration,
+    last_frame: Instant,
+    robots: BTreeMap<RobotId, Entity>,
+    // the characters of the name drawn above every robot
+    robot_labels: HashMap<RobotId, Vec<Entity>>,
+    chased_robot: Option<RobotId>,
+    trails: Trails,
+    show_trails: bool,
+    // (row, col) of the tiles and color of every path drawn on the Path layer, by name
+    planned_paths: BTreeMap<String, (Vec<(usize, usize)>, Color)>,
+    fog: FogOfWar,
+    minimap: Minimap,
+    // (row, col) of the tile under the cursor and of the one pinned to the inspector panel
+    hovered_tile: Option<(usize, usize)>,
+    inspected_tile: Option<(usize, usize)>,
+    show_inspector: bool,
+    // advanced by one fixed step at a time, so that the animations stay in sync with the robot
+    animation_time: Duration,
+    // fixed steps simulated so far
+    steps: u64,
 }
 
 impl<'window> MainState<'window> {
@@ -84,10 +163,44 @@ impl<'window> MainState<'window> {
             .build()
             .expect("could not create canvas");
 
-        let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG)?;
+        let texture_creator = canvas.texture_creator();
+
+        MainState::with_backend(
+            sdl_context,
+            RenderCanvas::Window(canvas),
+            RenderTextureCreator::Window(texture_creator),
+            robot_speed,
+        )
+    }
+
+    /// Creates a [`MainState`] that renders into an offscreen software surface instead of a
+    /// window, so that it can run on machines without a display.
+    pub fn new_headless(robot_speed: i32) -> Result<MainState<'window>, String> {
+        let sdl_context = sdl2::init()?;
+
+        let canvas = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32)?.into_canvas()?;
 
         let texture_creator = canvas.texture_creator();
 
+        MainState::with_backend(
+            sdl_context,
+            RenderCanvas::Headless(canvas),
+            RenderTextureCreator::Headless(texture_creator),
+            robot_speed,
+        )
+    }
+
+    fn with_backend(
+        sdl_context: Sdl,
+        canvas: RenderCanvas,
+        texture_creator: RenderTextureCreator,
+        robot_speed: i32,
+    ) -> Result<MainState<'window>, String> {
+        let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG)?;
+
+        let event_pump = sdl_context.event_pump()?;
+        let atlas = texture_creator.load_texture_bytes(DEFAULT_ATLAS)?;
+
         //world per le time
         let mut game_world = World::new();
         game_world.register::<Position>();
@@ -98,6 +211,8 @@ impl<'window> MainState<'window> {
         robot_world.register::<Velocity>();
         robot_world.register::<Position>();
         robot_world.register::<Sprite>();
+        robot_world.register::<Robot>();
+        robot_world.register::<Attached>();
 
         //world per i content
         let mut content_world = World::new();
@@ -112,6 +227,14 @@ impl<'window> MainState<'window> {
         overlay_world_markers.register::<Position>();
         overlay_world_markers.register::<Sprite>();
 
+        let mut trail_world = World::new();
+        trail_world.register::<Position>();
+        trail_world.register::<Sprite>();
+
+        let mut path_world = World::new();
+        path_world.register::<Position>();
+        path_world.register::<Sprite>();
+
         let mut weather_world = World::new();
         weather_world.register::<Position>();
         weather_world.register::<Sprite>();
@@ -120,12 +243,14 @@ impl<'window> MainState<'window> {
         time_world.register::<Position>();
         time_world.register::<Sprite>();
 
-        robot_world.insert(Some(Direction::Right));
-
         //chiama i system relativi al robot
         let mut dispatcher = DispatcherBuilder::new()
             .with(ChangeDirectionSystem, "ChangeDir", &[])
             .with(MoveSystem, "Movement", &["ChangeDir"])
+            .with(AttachSystem, "Attach", &["Movement"])
+            .with(CameraFollowSystem, "CameraFollow", &["Movement"])
+            .with(RobotSpriteSystem, "RobotSprite", &["ChangeDir"])
+            .with(AnimationSystem, "Animation", &["RobotSprite"])
             .build();
 
         dispatcher.setup(&mut robot_world);
@@ -136,23 +261,30 @@ impl<'window> MainState<'window> {
         sprite_table.load_default_sprites();
         sprite_table.load_default_font();
 
-        let camera = Camera {
-            screen_offset: (0, 0),
-            chase_robot: false,
-            zoom_level: 0,
-            robot_position: Point::new(0, 0),
-        };
+        let camera = Camera::new(canvas.output_size());
+        robot_world.insert(camera.clone());
+        robot_world.insert(ChasedRobot::default());
+        robot_world.insert(RobotSprites::new(&sprite_table));
 
         let mut worlds = HashMap::new();
 
         worlds.insert(Layer::Tiles, game_world);
         worlds.insert(Layer::Content, content_world);
+        worlds.insert(Layer::Trail, trail_world);
+        worlds.insert(Layer::Path, path_world);
         worlds.insert(Layer::Robot, robot_world);
         worlds.insert(Layer::Weather, weather_world);
         worlds.insert(Layer::OverlayHint, overlay_world_markers);
         worlds.insert(Layer::OverlayHover, overlay_world_hover);
         worlds.insert(Layer::Time, time_world);
 
+        // any drawable can be animated, the animations are advanced in step()
+        for world in worlds.values_mut() {
+            world.register::<Animated>();
+            world.register::<Tint>();
+            world.insert(AnimationClock::default());
+        }
+
         let ui_elements = HashMap::new();
 
         if robot_speed > 6 || robot_speed < 1 {
@@ -163,7 +295,7 @@ impl<'window> MainState<'window> {
         menus.push(Menu::new(MenuTypes::Markers));
 
         Ok(MainState {
-            sdl_context,
+            _sdl_context: sdl_context,
             canvas,
             worlds,
             energy: 0,
@@ -174,45 +306,409 @@ impl<'window> MainState<'window> {
             sprite_table,
             camera,
             tiles_world: Vec::new(),
+            cell_entities: HashMap::new(),
+            chunks: ChunkCache::new(),
             markers: Markers::new(),
             robot_speed,
             framerate: 60,
             scenes: Vec::new(),
             menus,
+            recorder: None,
+            cursor: Point::new(WIDTH as i32 / 2, HEIGHT as i32 / 2),
+            event_pump,
+            atlases: vec![atlas],
+            atlas_paths: vec![None],
+            hot_reload: false,
+            atlas_watchers: Vec::new(),
+            reload_error: None,
+            accumulator: Duration::ZERO,
+            animation_time: Duration::ZERO,
+            steps: 0,
+            last_frame: Instant::now(),
+            robots: BTreeMap::new(),
+            robot_labels: HashMap::new(),
+            chased_robot: None,
+            trails: Trails::new(),
+            show_trails: false,
+            planned_paths: BTreeMap::new(),
+            fog: FogOfWar::new(),
+            minimap: Minimap::new(),
+            hovered_tile: None,
+            inspected_tile: None,
+            show_inspector: true,
         })
     }
-    pub fn add_robot(&mut self, pos_x: usize, pos_y: usize) {
-        self.worlds
+    /// Adds a robot at the given tile and returns the id used to update it. The camera chases
+    /// the first robot until another one is picked with `chase_robot` or Tab.
+    pub fn add_robot(&mut self, pos_x: usize, pos_y: usize) -> RobotId {
+        let id = RobotId(self.robots.len());
+        let position = Point::new(TILE_SIZE * pos_x as i32, TILE_SIZE * pos_y as i32);
+        let entity = self
+            .worlds
             .get_mut(&Layer::Robot)
             .unwrap()
             .create_entity()
-            .with(Position(Point::new(
-                TILE_SIZE * pos_x as i32,
-                TILE_SIZE * pos_y as i32,
-            )))
-            .with(Velocity {
-                speed: 2_i32.pow(self.robot_speed as u32 - 1),
+            .with(Position(position))
+            .with(Velocity { direction: None })
+            .with(Robot {
+                id,
+                target: position,
                 direction: None,
+                remaining_steps: 0,
+                skin: None,
             })
-            .with(Sprite {
-                region: Rect::new(0, TILE_SIZE * 2, TILE_SIZE as u32, TILE_SIZE as u32),
-                texture_type: TextureType::Robot,
-            })
+            .with(Sprite::new(&self.sprite_table, TextureType::Robot))
             .build();
+        self.robots.insert(id, entity);
+
+        if self.chased_robot.is_none() {
+            self.chased_robot = Some(id);
+            // moves the camera relative to the start position of the robot
+            self.camera.center_on((
+                (pos_x as i32 * TILE_SIZE) as f32,
+                (pos_y as i32 * TILE_SIZE) as f32,
+            ));
+        }
+        id
+    }
 
-        // moves the camera relative to the start position of the robot
-        self.camera.screen_offset.0 =
-            -1 * pos_x as i32 * TILE_SIZE + self.canvas.output_size().unwrap().0 as i32 / 2;
-        self.camera.screen_offset.1 =
-            -1 * pos_y as i32 * TILE_SIZE + self.canvas.output_size().unwrap().1 as i32 / 2;
+    /// Returns the ids of all the robots, in the order they have been added.
+    pub fn robots(&self) -> Vec<RobotId> {
+        self.robots.keys().copied().collect()
     }
 
-    pub fn update_world(&mut self, world: Vec<Vec<Option<Tile>>>) {
-        self.worlds.get_mut(&Layer::Tiles).unwrap().delete_all();
-        self.worlds.get_mut(&Layer::Content).unwrap().delete_all();
+    fn robot_entity(&self, id: RobotId) -> Result<Entity, String> {
+        self.robots
+            .get(&id)
+            .copied()
+            .ok_or_else(|| format!("unknown robot {:?}", id))
+    }
+
+    /// Multiplies the colors of the robot's sprite, so that robots sharing a sprite can be told
+    /// apart. `None` removes the tint.
+    pub fn set_robot_tint(&mut self, id: RobotId, tint: Option<Color>) -> Result<(), String> {
+        let entity = self.robot_entity(id)?;
+        let mut tints = self
+            .worlds
+            .get(&Layer::Robot)
+            .unwrap()
+            .write_storage::<Tint>();
+        match tint {
+            Some(color) => {
+                let _ = tints.insert(entity, Tint(color));
+            }
+            None => {
+                tints.remove(entity);
+            }
+        }
+        drop(tints);
 
+        self.redraw_trails();
+        Ok(())
+    }
+
+    /// Draws the robot with the region `(x, y, width, height)` of the default atlas instead of
+    /// the robot sprites, `None` goes back to them.
+    pub fn set_robot_skin(
+        &mut self,
+        id: RobotId,
+        skin: Option<(i32, i32, u32, u32)>,
+    ) -> Result<(), String> {
+        let entity = self.robot_entity(id)?;
+        let world = self.worlds.get(&Layer::Robot).unwrap();
+        if let Some(robot) = world.write_storage::<Robot>().get_mut(entity) {
+            robot.skin = skin.map(|(x, y, width, height)| SpriteRegion {
+                atlas: DEFAULT_ATLAS_ID,
+                rect: Rect::new(x, y, width, height),
+            });
+        }
+        Ok(())
+    }
+
+    /// Draws a name above the robot, `None` removes it.
+    pub fn set_robot_label(&mut self, id: RobotId, label: Option<&str>) -> Result<(), String> {
+        let entity = self.robot_entity(id)?;
+        let world = self.worlds.get_mut(&Layer::Robot).unwrap();
+
+        for character in self.robot_labels.remove(&id).unwrap_or_default() {
+            let _ = world.delete_entity(character);
+        }
+        let Some(label) = label else {
+            return Ok(());
+        };
+
+        // same spacing as the ui texts
+        let spacing =
+            (TILE_SIZE as f32 * 0.3 + (TILE_SIZE as f32 * 0.3 * LABEL_SCALE - 1.0)) as i32;
+        let start = -spacing * (label.chars().count() as i32 - 1) / 2;
+        let robot_position = world.read_storage::<Position>().get(entity).map(|p| p.0);
+
+        let mut characters = Vec::new();
+        for (i, c) in label.chars().enumerate() {
+            let texture_type = TextureType::FontCharater(c, LABEL_SCALE, false);
+            // characters missing from the font are left out
+            if self.sprite_table.region(&texture_type).is_none() {
+                continue;
+            }
+            let offset = Point::new(start + spacing * i as i32, -TILE_SIZE * 3 / 4);
+            let character = world
+                .create_entity()
+                .with(Position(
+                    robot_position.unwrap_or(Point::new(0, 0)) + offset,
+                ))
+                .with(Sprite::new(&self.sprite_table, texture_type))
+                .with(Attached {
+                    parent: entity,
+                    offset,
+                })
+                .build();
+            characters.push(character);
+        }
+        self.robot_labels.insert(id, characters);
+        Ok(())
+    }
+
+    /// Returns where the robot is drawn as `(x, y)` in map pixels, `TILE_SIZE` per tile. It is
+    /// only a multiple of the tile size once the robot stopped moving.
+    pub fn robot_position(&self, id: RobotId) -> Result<(i32, i32), String> {
+        let entity = self.robot_entity(id)?;
+        let world = self.worlds.get(&Layer::Robot).unwrap();
+        let position = world
+            .read_storage::<Position>()
+            .get(entity)
+            .map(|p| (p.0.x(), p.0.y()));
+        position.ok_or_else(|| format!("robot {:?} has no position", id))
+    }
+
+    /// Returns the `(row, col)` of every tile the robot has been moved to with `update_robot`,
+    /// oldest first.
+    pub fn robot_trail(&self, id: RobotId) -> Result<Vec<(usize, usize)>, String> {
+        self.robot_entity(id)?;
+        Ok(self.trails.get(id))
+    }
+
+    /// Forgets where the robot has been, its trail starts again from the next `update_robot`.
+    pub fn clear_robot_trail(&mut self, id: RobotId) -> Result<(), String> {
+        self.robot_entity(id)?;
+        self.trails.clear(id);
+        self.redraw_trails();
+        Ok(())
+    }
+
+    /// Shows or hides the trails of the robots, they can also be toggled with T.
+    pub fn set_trails_visible(&mut self, visible: bool) {
+        self.show_trails = visible;
+        self.redraw_trails();
+    }
+
+    // rebuilds the Trail layer from the recorded trails
+    fn redraw_trails(&mut self) {
+        let dots = if self.show_trails {
+            self.trails.fading()
+        } else {
+            Vec::new()
+        };
+        // a trail has the same color as its robot, so that they can be told apart
+        let colors = {
+            let tints = self
+                .worlds
+                .get(&Layer::Robot)
+                .unwrap()
+                .read_storage::<Tint>();
+            self.robots
+                .iter()
+                .map(|(id, entity)| (*id, tints.get(*entity).map_or(TRAIL_COLOR, |t| t.0)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let trail_world = self.worlds.get_mut(&Layer::Trail).unwrap();
+        trail_world.delete_all();
+        for (id, (row, col), alpha) in dots {
+            let color = colors.get(&id).copied().unwrap_or(TRAIL_COLOR);
+            trail_world
+                .create_entity()
+                .with(Position(Point::new(
+                    col as i32 * TILE_SIZE,
+                    row as i32 * TILE_SIZE,
+                )))
+                .with(Sprite {
+                    region: Rect::new(0, 0, 0, 0),
+                    atlas: DEFAULT_ATLAS_ID,
+                    texture_type: TextureType::Square(
+                        (TRAIL_DOT_SIZE, TRAIL_DOT_SIZE),
+                        Color::RGBA(color.r, color.g, color.b, alpha),
+                        false,
+                        false,
+                    ),
+                })
+                .build();
+        }
+    }
+
+    /// Draws a path through the centers of the given `(row, col)` tiles, with an arrow at its
+    /// end, e.g. the plan of a robot. A path that never leaves its first tile is drawn as a dot
+    /// on it. Paths with different names are shown at the same time, setting one again
+    /// replaces it.
+    pub fn set_planned_path(&mut self, name: &str, path: Vec<(usize, usize)>, color: Color) {
+        self.planned_paths.insert(name.to_string(), (path, color));
+        self.redraw_planned_paths();
+    }
+
+    pub fn clear_planned_path(&mut self, name: &str) {
+        if self.planned_paths.remove(name).is_some() {
+            self.redraw_planned_paths();
+        }
+    }
+
+    pub fn clear_planned_paths(&mut self) {
+        self.planned_paths.clear();
+        self.redraw_planned_paths();
+    }
+
+    // rebuilds the Path layer from the planned paths
+    fn redraw_planned_paths(&mut self) {
+        let world = self.worlds.get_mut(&Layer::Path).unwrap();
+        world.delete_all();
+
+        for (path, color) in self.planned_paths.values() {
+            let points = path
+                .iter()
+                .map(|(row, col)| Point::new(*col as i32 * TILE_SIZE, *row as i32 * TILE_SIZE))
+                .collect::<Vec<_>>();
+            // the same tile twice in a row does not make a segment
+            let mut segments = points
+                .windows(2)
+                .map(|w| (w[0], w[1]))
+                .filter(|(start, end)| start != end)
+                .collect::<Vec<_>>();
+
+            if segments.is_empty() {
+                if let Some(point) = points.first() {
+                    world
+                        .create_entity()
+                        .with(Position(*point))
+                        .with(Sprite {
+                            region: Rect::new(0, 0, 0, 0),
+                            atlas: DEFAULT_ATLAS_ID,
+                            texture_type: TextureType::Square(
+                                (PATH_DOT_SIZE, PATH_DOT_SIZE),
+                                *color,
+                                false,
+                                false,
+                            ),
+                        })
+                        .build();
+                }
+                continue;
+            }
+            if let Some((start, end)) = segments.last().copied() {
+                segments.extend(arrow_head(start, end));
+            }
+            for (start, end) in segments {
+                world
+                    .create_entity()
+                    .with(Position(start))
+                    .with(Sprite {
+                        region: Rect::new(0, 0, 0, 0),
+                        atlas: DEFAULT_ATLAS_ID,
+                        texture_type: TextureType::Line(end, *color),
+                    })
+                    .build();
+            }
+        }
+    }
+
+    /// Covers the tiles that have never been seen with fog and, once a visibility radius has
+    /// been set, dims the known tiles that no robot sees right now.
+    pub fn set_fog_of_war(&mut self, enabled: bool) {
+        self.fog.enabled = enabled;
+    }
+
+    /// Sets how many tiles around itself a robot sees with the fog of war enabled, `None`
+    /// shows every known tile as if it was in sight.
+    pub fn set_visibility_radius(&mut self, radius: Option<usize>) {
+        self.fog.radius = radius;
+    }
+
+    // the (row, col) of the tile every robot is on or moving to
+    fn robot_tiles(&self) -> Vec<(usize, usize)> {
+        let robot_world = self.worlds.get(&Layer::Robot).unwrap();
+        let robots = robot_world.read_storage::<Robot>();
+        (&robots)
+            .join()
+            .map(|robot| {
+                (
+                    (robot.target.y() / TILE_SIZE).max(0) as usize,
+                    (robot.target.x() / TILE_SIZE).max(0) as usize,
+                )
+            })
+            .collect()
+    }
+
+    /// Shows the whole known map in the bottom right corner of the screen, with the robots,
+    /// the markers and the part of the map that is on screen. Clicking on it moves the camera
+    /// to the clicked tile, it can also be toggled with N.
+    pub fn set_minimap_visible(&mut self, visible: bool) {
+        self.minimap.visible = visible;
+    }
+
+    // the (row, col) of the known map under a point of the screen
+    fn tile_under(&self, point: Point) -> Option<(usize, usize)> {
+        let (col, row) = self.get_coords_from_pos(point);
+        if row < 0 || col < 0 {
+            return None;
+        }
+        let (row, col) = (row as usize, col as usize);
+        self.tiles_world
+            .get(row)
+            .filter(|tiles| col < tiles.len())
+            .map(|_| (row, col))
+    }
+
+    /// Shows or hides the tile inspector panel, it can also be toggled with P. A pinned tile
+    /// stays pinned while the panel is hidden.
+    pub fn set_inspector_visible(&mut self, visible: bool) {
+        self.show_inspector = visible;
+        self.refresh_inspector();
+    }
+
+    // shows the pinned tile, or else the one under the cursor, in the inspector panel
+    fn refresh_inspector(&mut self) {
+        MainState::clear_scene_by_name(self, INSPECTOR_SCENE.to_string());
+        if !self.show_inspector {
+            return;
+        }
+        match (self.inspected_tile, self.hovered_tile) {
+            (Some(tile), _) => draw_inspector(self, tile, true),
+            (None, Some(tile)) => draw_inspector(self, tile, false),
+            (None, None) => {}
+        }
+    }
+
+    /// Makes the camera chase the given robot.
+    pub fn chase_robot(&mut self, id: RobotId) -> Result<(), String> {
+        let entity = self.robot_entity(id)?;
+        self.chased_robot = Some(id);
+
+        let robot_position = self
+            .worlds
+            .get(&Layer::Robot)
+            .unwrap()
+            .read_storage::<Position>()
+            .get(entity)
+            .map(|p| p.0);
+        if let Some(position) = robot_position {
+            self.camera.robot_position = position;
+            self.camera
+                .center_on((position.x() as f32, position.y() as f32));
+        }
+        Ok(())
+    }
+
+    /// Replaces the known map, only the cells that differ from the previous map are redrawn.
+    pub fn update_world(&mut self, world: Vec<Vec<Option<Tile>>>) {
         let zoom_text = Text::new(
-            format!("zoom: {}", self.camera().zoom_level.to_string()),
+            format!("zoom: {:.2}", self.camera().scale()),
             (20, 90),
             0.5,
             true,
@@ -224,116 +720,172 @@ impl<'window> MainState<'window> {
         scena_zoom.add_element(Box::new(zoom_text.clone()));
         scena_zoom.draw(self);
 
-        self.tiles_world = world.clone();
+        // cells that are missing in one of the two maps count as unknown tiles
+        let rows = world.len().max(self.tiles_world.len());
+        for row in 0..rows {
+            let new_row = world.get(row);
+            let old_row = self.tiles_world.get(row);
+            let cols = new_row
+                .map_or(0, |r| r.len())
+                .max(old_row.map_or(0, |r| r.len()));
+
+            for col in 0..cols {
+                let new = new_row.and_then(|r| r.get(col)).and_then(|t| t.as_ref());
+                let old = old_row.and_then(|r| r.get(col)).and_then(|t| t.as_ref());
+                if new != old {
+                    self.chunks.invalidate(row, col);
+                    self.minimap.invalidate(row, col);
+                    MainState::update_cell(
+                        &mut self.worlds,
+                        &mut self.cell_entities,
+                        &self.sprite_table,
+                        (row, col),
+                        new,
+                    );
+                }
+            }
+        }
 
-        let mut y = 0;
-        let mut x;
+        self.tiles_world = world;
+        self.refresh_inspector();
+    }
 
-        //let min_coords = calculate_map_coords(Point::new(0, 0), &self.camera, &self.canvas);
-        let max_coords = calculate_map_coords(
-            Point::new(WIDTH as i32, HEIGHT as i32),
-            &self.camera,
-            &self.canvas,
-        );
-        //if y < min_coords.y() / 2 {
-        //   y = min_coords.y() / 2
-        //}
-        for rows in world.iter() {
-            x = 0;
-
-            if y > max_coords.y() * 2 {
-                break;
-            }
-            //   if x < min_coords.x() / 2 {
-            //      x = min_coords.x() / 2
-            // }
-
-            for cols in rows {
-                if x > max_coords.x() * 2 {
-                    break;
-                }
+    /// Applies the changes to single cells of the known map, given as `(row, col, tile)`, for
+    /// callers that already know which tiles changed since the last update.
+    pub fn update_tiles(&mut self, changes: &[(usize, usize, Option<Tile>)]) {
+        for (row, col, tile) in changes {
+            let (row, col) = (*row, *col);
 
-                match cols {
-                    Some(t) => {
-                        MainState::add_drawable(
-                            &mut self.worlds,
-                            &self.sprite_table,
-                            Layer::Tiles,
-                            TextureType::Tile(t.tile_type),
-                            x * TILE_SIZE,
-                            y * TILE_SIZE,
-                        );
-
-                        let content = get_texture_type_from_content(t.content.clone());
-
-                        match content {
-                            TextureType::Content(Content::None) => {}
-                            _ => {
-                                MainState::add_drawable(
-                                    &mut self.worlds,
-                                    &self.sprite_table,
-                                    Layer::Content,
-                                    content,
-                                    x * TILE_SIZE,
-                                    y * TILE_SIZE,
-                                );
-                            }
-                        }
-                    }
-                    None => {}
-                }
-                x += 1;
+            if self.tiles_world.len() <= row {
+                self.tiles_world.resize_with(row + 1, Vec::new);
             }
-            y += 1;
+            if self.tiles_world[row].len() <= col {
+                self.tiles_world[row].resize(col + 1, None);
+            }
+            if self.tiles_world[row][col] == *tile {
+                continue;
+            }
+
+            self.chunks.invalidate(row, col);
+            self.minimap.invalidate(row, col);
+            MainState::update_cell(
+                &mut self.worlds,
+                &mut self.cell_entities,
+                &self.sprite_table,
+                (row, col),
+                tile.as_ref(),
+            );
+            self.tiles_world[row][col] = tile.clone();
+        }
+        self.refresh_inspector();
+    }
+
+    // brings the tile and content entities of a single cell in line with the given tile
+    fn update_cell(
+        worlds: &mut HashMap<Layer, World>,
+        cell_entities: &mut CellEntities,
+        sprite_table: &SpriteTable,
+        (row, col): (usize, usize),
+        tile: Option<&Tile>,
+    ) {
+        let (tile_entity, content_entity) = cell_entities.remove(&(row, col)).unwrap_or_default();
+        let (x, y) = (col as i32 * TILE_SIZE, row as i32 * TILE_SIZE);
+
+        let tile_type = tile.map(|t| TextureType::Tile(t.tile_type));
+        let content = tile
+            .map(|t| TextureType::Content(t.content.clone()))
+            .filter(|c| *c != TextureType::Content(Content::None));
+
+        let tile_entity = MainState::update_drawable(
+            worlds,
+            sprite_table,
+            Layer::Tiles,
+            tile_entity,
+            tile_type,
+            x,
+            y,
+        );
+        let content_entity = MainState::update_drawable(
+            worlds,
+            sprite_table,
+            Layer::Content,
+            content_entity,
+            content,
+            x,
+            y,
+        );
+
+        if tile_entity.is_some() || content_entity.is_some() {
+            cell_entities.insert((row, col), (tile_entity, content_entity));
         }
     }
 
+    /// Moves a robot to `coords`, given as `(row, col)`. The robot is drawn moving there from
+    /// wherever it is on screen, unless it jumped onto a teleport or farther than
+    /// [`SNAP_DISTANCE`] tiles, then it appears there right away. `None` leaves everything as
+    /// it is.
     pub fn update_robot(
         &mut self,
+        id: RobotId,
         coords: Option<(usize, usize)>,
-        last_coords: Option<(usize, usize)>,
-    ) {
-        //usare le coordinate per calcolare la direzione
-        match coords {
-            Some(coords) => {
-                let last = last_coords.unwrap();
-                let dir;
-                if (coords.0 as i32 - last.0 as i32) > 0 {
-                    dir = Some(Direction::Down);
-                } else if (coords.0 as i32 - last.0 as i32) < 0 {
-                    dir = Some(Direction::Up);
-                } else if (coords.1 as i32 - last.1 as i32) > 0 {
-                    dir = Some(Direction::Right);
-                } else if (coords.1 as i32 - last.1 as i32) < 0 {
-                    dir = Some(Direction::Left);
-                } else {
-                    //println!("Not moved");
-                    dir = None
-                }
+    ) -> Result<(), String> {
+        let entity = self.robot_entity(id)?;
 
-                self.worlds
-                    .get_mut(&Layer::Robot)
-                    .unwrap()
-                    .insert(Some(dir.clone()));
-            }
-            None => {}
+        let Some((row, col)) = coords else {
+            return Ok(());
         };
+        let target = Point::new(col as i32 * TILE_SIZE, row as i32 * TILE_SIZE);
+        let on_teleport = self
+            .tiles_world
+            .get(row)
+            .and_then(|r| r.get(col))
+            .and_then(|t| t.as_ref())
+            .is_some_and(|t| matches!(t.tile_type, TileType::Teleport(_)));
+        let frames = self.frames_per_move();
+
+        let world = self.worlds.get(&Layer::Robot).unwrap();
+        let mut robots = world.write_storage::<Robot>();
+        let mut positions = world.write_storage::<Position>();
+        if let (Some(robot), Some(pos)) = (robots.get_mut(entity), positions.get_mut(entity)) {
+            // the jump is measured from the last known tile, not from where the robot is drawn
+            let jump = ((target.x() - robot.target.x()).abs()
+                + (target.y() - robot.target.y()).abs())
+                / TILE_SIZE;
+
+            if jump > SNAP_DISTANCE || (on_teleport && jump > 1) {
+                pos.0 = target;
+                robot.direction = None;
+                robot.remaining_steps = 0;
+            } else {
+                robot.direction = direction_towards(pos.0, target);
+                robot.remaining_steps = frames;
+            }
+            robot.target = target;
+        }
+        drop((robots, positions));
+
+        if self.trails.push(id, (row, col)) {
+            self.redraw_trails();
+        }
+
+        // the position shown is the one of the robot the camera follows
+        if self.chased_robot != Some(id) {
+            return Ok(());
+        }
 
         //self.worlds.get_mut(ORD_UI).unwrap().delete_all();
 
         MainState::clear_scene_by_name(self, "pos".to_string());
 
         let mut pos_scene = Scene::new("pos".to_string(), 1);
-        let pos_text = Text::new(
-            format!("x: {}, y: {}", coords.unwrap().1, coords.unwrap().0),
-            (20, 50),
-            1.0,
-            true,
-            1,
-        );
+        let pos_text = Text::new(format!("x: {}, y: {}", col, row), (20, 50), 1.0, true, 1);
         pos_scene.add_element(Box::new(pos_text));
         pos_scene.draw(self);
 
+        self.redraw_menus();
+        Ok(())
+    }
+    fn redraw_menus(&mut self) {
         // let menus = self.menus.iter().collect::<Vec<_>>().clone();
         // for menu in menus {
         //     if menu.1.is_open {
@@ -349,8 +901,6 @@ impl<'window> MainState<'window> {
         }
     }
     pub fn update_time_of_day(&mut self, time: DayTime) {
-        let limits = self.get_drawable_indexes();
-
         self.worlds.get_mut(&Layer::Time).unwrap().delete_all();
 
         MainState::add_drawable(
@@ -385,237 +935,694 @@ impl<'window> MainState<'window> {
         weather_scene.draw(self);
     }
 
-    /// Returns the get drawable indexes of this [`MainState`].
-    //min e max
-    fn get_drawable_indexes(&mut self) -> (Point, Point) {
-        let (window_width, window_height) = self.canvas.output_size().unwrap();
-        let min_coords = calculate_map_coords(Point::new(0, 0), &self.camera, &self.canvas);
-        let max_coords = calculate_map_coords(
-            Point::new(window_width as i32, window_height as i32),
-            &self.camera,
-            &self.canvas,
-        );
-        (min_coords, max_coords)
+    /// Returns the `(col, row)` of the first and last tile that are at least partially visible.
+    pub fn get_visible_tiles(&self) -> ((i32, i32), (i32, i32)) {
+        self.camera.visible_tiles()
     }
 
+    /// Blocking helper: handles the events and renders frames until the robot has finished
+    /// moving to the position given to the last `update_robot`.
     pub fn tick(&mut self) -> Result<(), String> {
-        let mut texture = self.texture_creator.load_texture(
-            Path::new(env!("CARGO_MANIFEST_DIR"))
-                .join("assets")
-                .join("texture.png"),
-        )?;
-
-        for _i in 0..(TILE_SIZE / 2_i32.pow(self.robot_speed as u32 - 1)) {
-            let mut event_pump = self.sdl_context.event_pump().unwrap();
-
-            //Event handling
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => {
-                        return Err("quit".to_string());
-                    }
-                    Event::MouseWheel { y: 1, .. } => {
-                        self.camera.zoom_level += 1;
-                    }
-                    Event::MouseWheel { y: -1, .. } => {
-                        if self.camera.zoom_level > -31 {
-                            self.camera.zoom_level -= 1;
-                        }
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::I),
-                        repeat: false,
-                        ..
-                    } => {
-                        let mut menus = self.menus.clone();
-                        for menu in menus.iter_mut() {
-                            if menu.get_menu_type() == MenuTypes::Inventory {
-                                menu.toggle();
-                            }
-                        }
-                        self.menus = menus;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::M),
-                        repeat: false,
-                        ..
-                    } => {
-                        let mut menus = self.menus.clone();
-                        for menu in menus.iter_mut() {
-                            if menu.get_menu_type() == MenuTypes::Markers {
-                                menu.toggle();
-                            }
-                        }
-                        self.menus = menus;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Left),
-                        repeat: false,
-                        ..
-                    } => {
-                        self.camera.screen_offset.0 += TILE_SIZE;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Right),
-                        repeat: false,
-                        ..
-                    } => {
-                        self.camera.screen_offset.0 -= TILE_SIZE;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Down),
-                        repeat: false,
-                        ..
-                    } => {
-                        self.camera.screen_offset.1 -= TILE_SIZE;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Up),
-                        repeat: false,
-                        ..
-                    } => {
-                        self.camera.screen_offset.1 += TILE_SIZE;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::R),
-                        repeat: false,
-                        ..
-                    } => {
-                        self.camera.screen_offset = (0, 0);
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Space),
-                        repeat: false,
-                        ..
-                    } => {
-                        let (window_width, window_height) = self.canvas.output_size().unwrap();
-                        self.camera.chase_robot = !self.camera.chase_robot;
-                        self.camera.screen_offset = (
-                            -self.camera.robot_position.x() + window_width as i32 / 2,
-                            -self.camera.robot_position.y() + window_height as i32 / 2,
-                        );
-                        self.camera.zoom_level = 0
-                    }
-                    Event::MouseButtonDown {
-                        mouse_btn, x, y, ..
-                    } => match mouse_btn {
-                        sdl2::mouse::MouseButton::Middle => {
-                            let pos = self.get_coords_from_pos(Point::new(x, y));
-                            self.markers.toggle(pos);
-
-                            self.worlds
-                                .get_mut(&Layer::OverlayHint)
-                                .unwrap()
-                                .delete_all();
-
-                            for marker in &self.markers.get_all() {
-                                MainState::add_drawable(
-                                    &mut self.worlds,
-                                    &self.sprite_table,
-                                    Layer::OverlayHint,
-                                    TextureType::Overlay(OverlayType::TileMarker),
-                                    marker.0 .1 * TILE_SIZE,
-                                    marker.0 .0 * TILE_SIZE,
-                                );
-                            }
-                        }
-                        _ => {}
-                    },
-                    Event::MouseMotion {
-                        mousestate,
-                        xrel,
-                        yrel,
-                        y,
-                        x,
-                        ..
-                    } => {
-                        if mousestate.right() {
-                            self.camera.screen_offset.0 += xrel;
-                            self.camera.screen_offset.1 += yrel;
-                        }
-                        let pos = self.get_coords_from_pos(Point::new(x, y));
-                        // println!( "Pointing: {:?} z:{:?}, camera offset: {:?}", pos, self.camera.zoom_level, self.camera.screen_offset);
-                        if self.tiles_world.len() > pos.1 as usize
-                            && self.tiles_world[0].len() > pos.0 as usize
-                        {
-                            self.worlds
-                                .get_mut(&Layer::OverlayHover)
-                                .unwrap()
-                                .delete_all();
-
-                            MainState::add_drawable(
-                                &mut self.worlds,
-                                &self.sprite_table,
-                                Layer::OverlayHover,
-                                TextureType::Overlay(OverlayType::TileHover),
-                                pos.0 * TILE_SIZE,
-                                pos.1 * TILE_SIZE,
-                            );
-                            //println!( "Pointing tile {:?}", self.tiles_world[pos.1 as usize][pos.0 as usize])
-                        }
+        loop {
+            self.poll_events()?;
+            self.render_frame()?;
+
+            if !self.is_animating() {
+                return Ok(());
+            }
+            self.wait_for_next_step();
+        }
+    }
+
+    /// Handles all the pending input events without blocking, returns `Err("quit")` once the
+    /// window has been closed or Esc has been pressed.
+    pub fn poll_events(&mut self) -> Result<(), String> {
+        let events = self.event_pump.poll_iter().collect::<Vec<_>>();
+        for event in events {
+            self.handle_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Advances the animations by as many fixed steps as the time elapsed since the last frame
+    /// requires and draws the result, without blocking.
+    pub fn render_frame(&mut self) -> Result<(), String> {
+        let now = Instant::now();
+        let step = self.step_duration();
+
+        // a headless run is not watched by anyone, so every frame is exactly one step long and
+        // the output does not depend on how fast the machine is
+        if self.canvas.is_headless() {
+            self.accumulator += step;
+        } else {
+            self.accumulator += now - self.last_frame;
+        }
+        self.last_frame = now;
+        self.accumulator = self.accumulator.min(step * MAX_STEPS_PER_FRAME);
+
+        while self.accumulator >= step {
+            self.accumulator -= step;
+            self.step();
+        }
+
+        self.reload_changed_atlases();
+        self.draw_frame();
+
+        // the frame has to be read before presenting, the back buffer is undefined afterwards
+        if let Some(path) = self.recorder.as_mut().and_then(|r| r.next_frame()) {
+            let (width, height, pixels) = self.canvas.read_pixels()?;
+            save_png(width, height, pixels, &path)?;
+        }
+
+        self.canvas.present();
+        Ok(())
+    }
+
+    /// Loads the default texture atlas from `<root>/texture.png` instead of the one embedded in
+    /// the crate.
+    pub fn set_asset_root<P: AsRef<Path>>(&mut self, root: P) -> Result<(), String> {
+        let path = root.as_ref().join(ATLAS_FILE);
+        let atlas = self.texture_creator.load_texture(&path)?;
+
+        self.swap_atlas(DEFAULT_ATLAS_ID, atlas);
+        self.atlas_paths[DEFAULT_ATLAS_ID] = Some(path);
+        self.watch_atlases();
+        Ok(())
+    }
+
+    /// Replaces the sprites and the texture atlases with the ones described by an atlas
+    /// manifest, see `assets/atlas.json` for the format. Nothing changes if the manifest is not
+    /// valid or one of its textures cannot be loaded.
+    pub fn load_sprite_atlas<P: AsRef<Path>>(&mut self, manifest: P) -> Result<(), String> {
+        let (sprite_table, paths) = SpriteTable::from_file(manifest)?;
+
+        let mut atlases = Vec::new();
+        for path in &paths {
+            match self.texture_creator.load_texture(path) {
+                Ok(atlas) => atlases.push(atlas),
+                Err(e) => {
+                    for atlas in atlases {
+                        destroy_texture(atlas);
                     }
-                    _ => {}
+                    return Err(format!("{}: {}", path.display(), e));
                 }
             }
+        }
 
-            //UPDATE
-            self.dispatcher
-                .dispatch(&self.worlds.get_mut(&Layer::Robot).unwrap());
+        for old in std::mem::replace(&mut self.atlases, atlases) {
+            destroy_texture(old);
+        }
+        self.atlas_paths = paths.into_iter().map(Some).collect();
+        self.chunks.invalidate_all();
+        self.watch_atlases();
+
+        self.sprite_table = sprite_table;
+        MainState::refresh_sprites(&mut self.worlds, &mut self.ui_elements, &self.sprite_table);
+        self.worlds
+            .get_mut(&Layer::Robot)
+            .unwrap()
+            .insert(RobotSprites::new(&self.sprite_table));
+        Ok(())
+    }
 
-            for world in self.worlds.values_mut() {
-                world.maintain();
+    /// Reloads the atlases while the application is running every time their files change on
+    /// disk, they have to be loaded with `set_asset_root` or `load_sprite_atlas` first.
+    pub fn set_hot_reload(&mut self, enabled: bool) -> Result<(), String> {
+        if enabled && self.atlas_paths.iter().all(Option::is_none) {
+            return Err("the embedded atlas cannot be reloaded, load one from disk first".into());
+        }
+        self.hot_reload = enabled;
+        self.watch_atlases();
+        Ok(())
+    }
+
+    // starts watching the files the atlases have been loaded from again
+    fn watch_atlases(&mut self) {
+        self.atlas_watchers.clear();
+        if !self.hot_reload {
+            return;
+        }
+        for (id, path) in self.atlas_paths.iter().enumerate() {
+            if let Some(path) = path {
+                self.atlas_watchers
+                    .push((id, FileWatcher::new(path.clone())));
             }
+        }
+    }
 
-            self.canvas.clear();
-            for layer in RENDER_ORDER {
-                let _ = render_sprites(
-                    &mut self.canvas,
-                    &mut texture,
-                    self.worlds.get(&layer).unwrap().system_data(),
-                    &mut self.camera,
-                );
+    /// Returns why the last automatic reload of an atlas failed, if it did, and forgets it.
+    /// The previous atlas stays in use until the file can be loaded.
+    pub fn take_reload_error(&mut self) -> Option<String> {
+        self.reload_error.take()
+    }
+
+    fn reload_changed_atlases(&mut self) {
+        let changed = self
+            .atlas_watchers
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, (id, watcher))| {
+                watcher
+                    .changed()
+                    .then(|| (i, *id, watcher.path().to_path_buf()))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, id, path) in changed {
+            match self.texture_creator.load_texture(&path) {
+                Ok(atlas) => self.swap_atlas(id, atlas),
+                Err(e) => {
+                    // a png that is still being written fails to load, it is tried again at
+                    // the next check until it is complete
+                    self.atlas_watchers[i].1.retry();
+                    self.reload_error = Some(format!("{}: {}", path.display(), e));
+                }
             }
+        }
+    }
+
+    fn swap_atlas(&mut self, id: AtlasId, atlas: Texture) {
+        let old = std::mem::replace(&mut self.atlases[id], atlas);
+        destroy_texture(old);
+        self.chunks.invalidate_all();
+    }
 
-            let mut tmp = self.ui_elements.iter().collect::<Vec<_>>();
-            tmp.sort_by_key(|a| (a.0 .1, a.0 .2));
-            //println!("{:?}", tmp.iter().map(|x| x.0).collect::<Vec<_>>());
+    /// Returns true while any robot is still moving towards the position given to its last
+    /// `update_robot`.
+    pub fn is_animating(&self) -> bool {
+        let robot_world = self.worlds.get(&Layer::Robot).unwrap();
+        let robots = robot_world.read_storage::<Robot>();
+        (&robots).join().any(|robot| robot.remaining_steps > 0)
+    }
+
+    // frames a robot needs to reach the tile given to update_robot
+    fn frames_per_move(&self) -> u32 {
+        (TILE_SIZE / 2_i32.pow(self.robot_speed as u32 - 1)) as u32
+    }
 
-            let elements = tmp.iter().map(|x| x.1).collect::<Vec<_>>();
-            for element in elements {
-                let _ = render_sprites(
+    fn step_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.framerate as f64)
+    }
+
+    // sleeps until the next fixed step is due
+    fn wait_for_next_step(&self) {
+        if self.canvas.is_headless() {
+            return;
+        }
+        let next_step = self.step_duration().saturating_sub(self.accumulator);
+        std::thread::sleep(next_step.saturating_sub(self.last_frame.elapsed()));
+    }
+
+    // a single fixed step of the simulation
+    fn step(&mut self) {
+        self.steps += 1;
+        self.camera.update_zoom();
+
+        self.animation_time += self.step_duration();
+        for world in self.worlds.values_mut() {
+            world.insert(AnimationClock(self.animation_time));
+        }
+        // the robot world runs the animations in its dispatcher
+        for layer in [Layer::Tiles, Layer::Content] {
+            AnimationSystem.run_now(self.worlds.get(&layer).unwrap());
+        }
+
+        let robot_world = self.worlds.get_mut(&Layer::Robot).unwrap();
+        // the systems work on their own copy of the camera, it is read back once they are done
+        robot_world.insert(self.camera.clone());
+        robot_world.insert(ChasedRobot(self.chased_robot));
+        self.dispatcher.dispatch(robot_world);
+        self.camera = (*robot_world.read_resource::<Camera>()).clone();
+
+        for world in self.worlds.values_mut() {
+            world.maintain();
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Result<(), String> {
+        match event {
+            Event::Quit { .. }
+            | Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => {
+                return Err("quit".to_string());
+            }
+            Event::MouseWheel { y, .. } if y != 0 => {
+                self.camera.zoom_towards(
+                    (self.cursor.x() as f32, self.cursor.y() as f32),
+                    ZOOM_STEP.powi(y),
+                );
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::I),
+                repeat: false,
+                ..
+            } => {
+                let mut menus = self.menus.clone();
+                for menu in menus.iter_mut() {
+                    if menu.get_menu_type() == MenuTypes::Inventory {
+                        menu.toggle();
+                    }
+                }
+                self.menus = menus;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::M),
+                repeat: false,
+                ..
+            } => {
+                let mut menus = self.menus.clone();
+                for menu in menus.iter_mut() {
+                    if menu.get_menu_type() == MenuTypes::Markers {
+                        menu.toggle();
+                    }
+                }
+                self.menus = menus;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Left),
+                repeat: false,
+                ..
+            } => {
+                self.camera.pan.0 += TILE_SIZE as f32;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Right),
+                repeat: false,
+                ..
+            } => {
+                self.camera.pan.0 -= TILE_SIZE as f32;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Down),
+                repeat: false,
+                ..
+            } => {
+                self.camera.pan.1 -= TILE_SIZE as f32;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Up),
+                repeat: false,
+                ..
+            } => {
+                self.camera.pan.1 += TILE_SIZE as f32;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::R),
+                repeat: false,
+                ..
+            } => {
+                self.camera.set_pan((0.0, 0.0));
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Space),
+                repeat: false,
+                ..
+            } => {
+                self.camera.chase_robot = !self.camera.chase_robot;
+                self.camera.center_on((
+                    self.camera.robot_position.x() as f32,
+                    self.camera.robot_position.y() as f32,
+                ));
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Tab),
+                repeat: false,
+                ..
+            } => {
+                // chases the robot added after the current one, wrapping around to the first
+                let next = self
+                    .chased_robot
+                    .and_then(|chased| self.robots.range(chased..).nth(1))
+                    .or(self.robots.iter().next())
+                    .map(|(id, _)| *id);
+                if let Some(id) = next {
+                    self.chase_robot(id)?;
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::N),
+                repeat: false,
+                ..
+            } => {
+                self.minimap.visible = !self.minimap.visible;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::T),
+                repeat: false,
+                ..
+            } => {
+                self.show_trails = !self.show_trails;
+                self.redraw_trails();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::P),
+                repeat: false,
+                ..
+            } => {
+                self.show_inspector = !self.show_inspector;
+                self.refresh_inspector();
+            }
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => match mouse_btn {
+                sdl2::mouse::MouseButton::Left => {
+                    let clicked =
+                        self.minimap
+                            .tile_at(&self.tiles_world, &self.camera, Point::new(x, y));
+                    if let Some((row, col)) = clicked {
+                        // the camera would be pulled back to the chased robot right away
+                        self.camera.chase_robot = false;
+                        self.camera.center_on((
+                            (col as i32 * TILE_SIZE) as f32,
+                            (row as i32 * TILE_SIZE) as f32,
+                        ));
+                    } else if let Some(tile) = self.tile_under(Point::new(x, y)) {
+                        // clicking the pinned tile again unpins it
+                        self.inspected_tile = if self.inspected_tile == Some(tile) {
+                            None
+                        } else {
+                            Some(tile)
+                        };
+                        self.refresh_inspector();
+                    }
+                }
+                sdl2::mouse::MouseButton::Middle => {
+                    let pos = self.get_coords_from_pos(Point::new(x, y));
+                    self.markers.toggle(pos, self.steps);
+                    self.redraw_markers();
+                }
+                _ => {}
+            },
+            Event::MouseMotion {
+                mousestate,
+                xrel,
+                yrel,
+                y,
+                x,
+                ..
+            } => {
+                self.cursor = Point::new(x, y);
+                if mousestate.right() {
+                    self.camera.pan_by_screen(xrel as f32, yrel as f32);
+                }
+                let pos = self.get_coords_from_pos(Point::new(x, y));
+                // println!( "Pointing: {:?} z:{:?}, camera pan: {:?}", pos, self.camera.scale(), self.camera.pan());
+                if self.tiles_world.len() > pos.1 as usize
+                    && self.tiles_world[0].len() > pos.0 as usize
+                {
+                    self.worlds
+                        .get_mut(&Layer::OverlayHover)
+                        .unwrap()
+                        .delete_all();
+
+                    MainState::add_drawable(
+                        &mut self.worlds,
+                        &self.sprite_table,
+                        Layer::OverlayHover,
+                        TextureType::Overlay(OverlayType::TileHover),
+                        pos.0 * TILE_SIZE,
+                        pos.1 * TILE_SIZE,
+                    );
+                }
+                // leaving the map empties the panel, unless a tile is pinned
+                let hovered = self.tile_under(Point::new(x, y));
+                if hovered != self.hovered_tile {
+                    self.hovered_tile = hovered;
+                    self.refresh_inspector();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn draw_frame(&mut self) {
+        self.canvas.clear();
+        for layer in RENDER_ORDER {
+            // the tiles are drawn from the pre-rendered chunks when the renderer can draw on textures
+            if layer == Layer::Tiles && self.canvas.render_target_supported() {
+                let _ = self.chunks.render(
                     &mut self.canvas,
-                    &mut texture,
-                    element.system_data(),
-                    &mut self.camera,
+                    &self.texture_creator,
+                    &self.atlases,
+                    &self.tiles_world,
+                    &self.sprite_table,
+                    &self.camera,
+                    self.animation_time,
                 );
+                continue;
+            }
+            if layer == Layer::Fog {
+                let robots = self.robot_tiles();
+                let _ = self
+                    .fog
+                    .render(&mut self.canvas, &self.tiles_world, &robots, &self.camera);
+                continue;
             }
+            let _ = self.canvas.render_world(
+                &mut self.atlases,
+                self.worlds.get(&layer).unwrap(),
+                &self.camera,
+            );
+        }
+
+        let mut tmp = self.ui_elements.iter().collect::<Vec<_>>();
+        tmp.sort_by_key(|a| (a.0 .1, a.0 .2));
+        //println!("{:?}", tmp.iter().map(|x| x.0).collect::<Vec<_>>());
 
-            self.canvas.present();
-            std::thread::sleep(Duration::new(0, 1_000_000_000u32 / self.framerate));
+        let elements = tmp.iter().map(|x| x.1).collect::<Vec<_>>();
+        for element in elements {
+            let _ = self
+                .canvas
+                .render_world(&mut self.atlases, element, &self.camera);
         }
 
+        if self.minimap.visible {
+            let robots = {
+                let positions = self
+                    .worlds
+                    .get(&Layer::Robot)
+                    .unwrap()
+                    .read_storage::<Position>();
+                self.robots
+                    .values()
+                    .filter_map(|entity| positions.get(*entity).map(|p| p.0))
+                    .collect::<Vec<_>>()
+            };
+            let markers = self
+                .markers
+                .get_all()
+                .into_iter()
+                .map(|(coords, marker)| (coords, marker.color()))
+                .collect::<Vec<_>>();
+            let _ = self.minimap.render(
+                &mut self.canvas,
+                &self.texture_creator,
+                &self.tiles_world,
+                &robots,
+                &markers,
+                &self.camera,
+            );
+        }
+    }
+
+    /// Draws the current state of the world and the ui without presenting it and returns the
+    /// width, height and RGBA32 pixels of the frame.
+    pub fn read_frame(&mut self) -> Result<(u32, u32, Vec<u8>), String> {
+        self.draw_frame();
+        self.canvas.read_pixels()
+    }
+
+    /// Draws the current state of the world and the ui and writes it to `path` as a png.
+    pub fn capture_frame<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let (width, height, pixels) = self.read_frame()?;
+        save_png(width, height, pixels, path.as_ref())
+    }
+
+    /// Starts writing every `every_n_frames`-th frame drawn by `render_frame()` into `directory` as
+    /// `frame_000000.png`, `frame_000001.png`, ...
+    pub fn start_recording<P: AsRef<Path>>(
+        &mut self,
+        directory: P,
+        every_n_frames: u32,
+    ) -> Result<(), String> {
+        self.recorder = Some(FrameRecorder::new(
+            PathBuf::from(directory.as_ref()),
+            every_n_frames,
+        )?);
         Ok(())
     }
+
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
     //possibilmente cambiare la struct Point con tipi buildin per evitare di importare sdl nel main
     pub fn get_coords_from_pos(&self, pos: Point) -> (i32, i32) {
-        let point = renderer::calculate_map_coords(pos, &self.camera, &self.canvas);
-        ((point.x()), (point.y()))
+        self.camera.screen_to_map((pos.x() as f32, pos.y() as f32))
     }
     pub fn load_sprite(&mut self, tt: TextureType, x: i32, y: i32, height: u32, width: u32) {
-        self.sprite_table
-            .load_sprite(tt, Rect::new(x, y, width, height));
+        // squares and lines are not drawn from the atlas
+        if let Some(key) = tt.sprite_key() {
+            self.sprite_table
+                .load_sprite(key, Rect::new(x, y, width, height));
+        }
     }
+    /// Returns every marker with the `(row, col)` of its tile, oldest first.
     pub fn get_markers(&self) -> Vec<((i32, i32), Marker)> {
         self.markers.get_all()
     }
-    //TODO: implement method to delete markers
+    /// Marks the tile at `(row, col)`, replacing the marker that was already there. The marker
+    /// overlay is tinted with `color`.
+    pub fn add_marker(
+        &mut self,
+        coords: (usize, usize),
+        label: &str,
+        color: Color,
+        category: &str,
+    ) {
+        self.markers.insert(Marker::new(
+            (coords.0 as i32, coords.1 as i32),
+            label.to_string(),
+            color,
+            category.to_string(),
+            self.steps,
+        ));
+        self.redraw_markers();
+    }
+    /// Marks the tile at `(row, col)` like a middle click does.
+    pub fn add_default_marker(&mut self, coords: (usize, usize)) {
+        self.add_marker(coords, "", DEFAULT_MARKER_COLOR, DEFAULT_CATEGORY);
+    }
+    /// Removes the marker on the tile at `(row, col)` and returns it, if there was one.
+    pub fn remove_marker(&mut self, coords: (usize, usize)) -> Option<Marker> {
+        let removed = self.markers.remove((coords.0 as i32, coords.1 as i32));
+        if removed.is_some() {
+            self.redraw_markers();
+        }
+        removed
+    }
+    pub fn clear_markers(&mut self) {
+        self.markers.clear();
+        self.redraw_markers();
+    }
+    pub fn get_markers_by_category(&self, category: &str) -> Vec<((i32, i32), Marker)> {
+        self.markers.by_category(category)
+    }
+    /// Returns the markers inside the rectangle between the `(row, col)` corners `min` and
+    /// `max`, both included.
+    pub fn get_markers_in_region(
+        &self,
+        min: (usize, usize),
+        max: (usize, usize),
+    ) -> Vec<((i32, i32), Marker)> {
+        self.markers
+            .in_region((min.0 as i32, min.1 as i32), (max.0 as i32, max.1 as i32))
+    }
 
+    // rebuilds the marker overlay from the markers
+    fn redraw_markers(&mut self) {
+        self.worlds
+            .get_mut(&Layer::OverlayHint)
+            .unwrap()
+            .delete_all();
+
+        for ((row, col), marker) in self.markers.get_all() {
+            let entity = MainState::add_drawable(
+                &mut self.worlds,
+                &self.sprite_table,
+                Layer::OverlayHint,
+                TextureType::Overlay(OverlayType::TileMarker),
+                col * TILE_SIZE,
+                row * TILE_SIZE,
+            );
+            let _ = self
+                .worlds
+                .get(&Layer::OverlayHint)
+                .unwrap()
+                .write_storage::<Tint>()
+                .insert(entity, Tint(marker.color()));
+        }
+    }
+
+    /// Writes the markers, the camera position and zoom and the open menus to `path` as json,
+    /// so that they can be restored with `load_session`.
+    pub fn save_session<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        // the pan belongs to the current scale, a zoom that is still easing is saved where it is
+        let camera = CameraState {
+            pan: self.camera.pan(),
+            scale: self.camera.scale(),
+            chase_robot: self.camera.chase_robot,
+        };
+        let markers = self
+            .markers
+            .get_all()
+            .iter()
+            .map(|(coords, marker)| MarkerState::new(*coords, marker))
+            .collect();
+
+        Session::new(camera, self.open_menus(), markers).write(path.as_ref())
+    }
+    /// Restores a session written by `save_session`, the markers placed so far are replaced by
+    /// the saved ones. Nothing changes if the file can not be read.
+    pub fn load_session<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let session = Session::read(path)?;
+
+        let unknown = session
+            .open_menus
+            .iter()
+            .filter(|name| {
+                !self
+                    .menus
+                    .iter()
+                    .any(|menu| menu.get_menu_type().to_string() == **name)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "{}: unknown menus {}",
+                path.display(),
+                unknown.join(", ")
+            ));
+        }
+
+        self.markers.clear();
+        for marker in session.markers {
+            self.markers.insert(marker.into_marker());
+        }
+        self.redraw_markers();
+
+        self.camera.set_scale(session.camera.scale);
+        self.camera.set_pan(session.camera.pan);
+        self.camera.chase_robot = session.camera.chase_robot;
+
+        for menu in self.menus.iter_mut() {
+            menu.is_open = session
+                .open_menus
+                .contains(&menu.get_menu_type().to_string());
+        }
+        self.redraw_menus();
+        Ok(())
+    }
+
+    /// Sets how many fixed steps are simulated per second, at least 1.
     pub fn set_framerate(&mut self, framerate: u32) {
-        self.framerate = framerate
+        self.framerate = framerate.max(1)
+    }
+    /// Configures how the camera chases the robot: it only moves once the robot leaves the
+    /// `dead_zone` (width and height in screen pixels around the centre of the screen), covering
+    /// `easing` of the remaining distance every frame.
+    pub fn set_camera_follow(&mut self, dead_zone: (u32, u32), easing: f32) {
+        self.camera.set_follow(dead_zone, easing);
+    }
+    /// Limits how far the map can be zoomed out and in, `1.0` draws a tile with its original
+    /// size of [`TILE_SIZE`] pixels.
+    pub fn set_zoom_bounds(&mut self, min_scale: f32, max_scale: f32) {
+        self.camera.set_scale_bounds(min_scale, max_scale);
     }
 
     pub(crate) fn add_ui_element(
@@ -633,24 +1640,16 @@ impl<'window> MainState<'window> {
                     .or_insert(World::new());
                 world.register::<Position>();
                 world.register::<Sprite>();
+                world.register::<Tint>();
 
                 match &texture_type {
-                    TextureType::Item(item, _, _) => {
-                        world
-                            .create_entity()
-                            .with(Position(Point::new(x, y)))
-                            .with(Sprite {
-                                region: *sprite_table.0.get(&*item.clone()).unwrap(),
-                                texture_type,
-                            })
-                            .build();
-                    }
                     TextureType::Square(size, color, centered, fixed) => {
                         world
                             .create_entity()
                             .with(Position(Point::new(x, y)))
                             .with(Sprite {
                                 region: Rect::new(0, 0, 0, 0),
+                                atlas: DEFAULT_ATLAS_ID,
                                 texture_type: TextureType::Square(*size, *color, *centered, *fixed),
                             })
                             .build();
@@ -659,10 +1658,7 @@ impl<'window> MainState<'window> {
                         world
                             .create_entity()
                             .with(Position(Point::new(x, y)))
-                            .with(Sprite {
-                                region: *sprite_table.0.get(&texture_type).unwrap(),
-                                texture_type,
-                            })
+                            .with(Sprite::new(sprite_table, texture_type))
                             .build();
                     }
                 }
@@ -670,6 +1666,56 @@ impl<'window> MainState<'window> {
             _ => {}
         }
     }
+    // copies the regions and animations of a new sprite table into the sprites that are already
+    // on screen
+    fn refresh_sprites(
+        worlds: &mut HashMap<Layer, World>,
+        ui_elements: &mut HashMap<(String, u32, u32), World>,
+        sprite_table: &SpriteTable,
+    ) {
+        for world in worlds.values_mut().chain(ui_elements.values_mut()) {
+            for sprite in (&mut world.write_storage::<Sprite>()).join() {
+                if let Some(region) = sprite_table.region(&sprite.texture_type) {
+                    sprite.region = region.rect;
+                    sprite.atlas = region.atlas;
+                }
+            }
+        }
+
+        for world in worlds.values_mut() {
+            let drawables = (&world.entities(), &world.read_storage::<Sprite>())
+                .join()
+                .map(|(entity, sprite)| (entity, sprite.texture_type.clone()))
+                .collect::<Vec<_>>();
+            for (entity, texture_type) in drawables {
+                MainState::set_animation(world, entity, sprite_table, &texture_type);
+            }
+        }
+    }
+    // starts the animation of the texture type, if it has one, or stops the current one
+    fn set_animation(
+        world: &mut World,
+        entity: Entity,
+        sprite_table: &SpriteTable,
+        texture_type: &TextureType,
+    ) {
+        let mut animated = world.write_storage::<Animated>();
+        match sprite_table.texture_animation(texture_type) {
+            Some(animation) => {
+                // every animation starts at time zero, so that equal sprites stay in sync
+                let _ = animated.insert(
+                    entity,
+                    Animated {
+                        animation: animation.clone(),
+                        start: Duration::ZERO,
+                    },
+                );
+            }
+            None => {
+                animated.remove(entity);
+            }
+        }
+    }
     pub(crate) fn clear_scene_by_name(state: &mut MainState, name: String) {
         let keys = state
             .scenes
@@ -693,22 +1739,63 @@ impl<'window> MainState<'window> {
         texture_type: TextureType,
         x: i32,
         y: i32,
-    ) {
-        worlds
-            .get_mut(&layer)
-            .unwrap()
+    ) -> Entity {
+        let world = worlds.get_mut(&layer).unwrap();
+        let entity = world
             .create_entity()
             .with(Position(Point::new(x, y)))
-            .with(Sprite {
-                region: *sprite_table.0.get(&texture_type).unwrap(),
-                texture_type,
-            })
+            .with(Sprite::new(sprite_table, texture_type.clone()))
             .build();
+        MainState::set_animation(world, entity, sprite_table, &texture_type);
+        entity
+    }
+    // creates, updates or deletes an entity so that it draws the given texture, if any
+    pub(crate) fn update_drawable(
+        worlds: &mut HashMap<Layer, World>,
+        sprite_table: &SpriteTable,
+        layer: Layer,
+        entity: Option<Entity>,
+        texture_type: Option<TextureType>,
+        x: i32,
+        y: i32,
+    ) -> Option<Entity> {
+        match (entity, texture_type) {
+            (Some(entity), Some(texture_type)) => {
+                let world = worlds.get_mut(&layer).unwrap();
+                MainState::set_animation(world, entity, sprite_table, &texture_type);
+                if let Some(sprite) = world.write_storage::<Sprite>().get_mut(entity) {
+                    *sprite = Sprite::new(sprite_table, texture_type);
+                }
+                Some(entity)
+            }
+            (None, Some(texture_type)) => Some(MainState::add_drawable(
+                worlds,
+                sprite_table,
+                layer,
+                texture_type,
+                x,
+                y,
+            )),
+            (Some(entity), None) => {
+                let _ = worlds.get_mut(&layer).unwrap().delete_entity(entity);
+                None
+            }
+            (None, None) => None,
+        }
     }
 
-    pub(crate) fn camera(&self) -> &Camera {
+    /// The camera the map is drawn with.
+    pub fn camera(&self) -> &Camera {
         &self.camera
     }
+    /// Returns the names of the open menus, e.g. `"markers"`, as written by `save_session`.
+    pub fn open_menus(&self) -> Vec<String> {
+        self.menus
+            .iter()
+            .filter(|menu| menu.is_open())
+            .map(|menu| menu.get_menu_type().to_string())
+            .collect()
+    }
     pub fn update_energy(&mut self, energy: u32) {
         self.energy = energy;
 
@@ -726,3 +1813,51 @@ impl<'window> MainState<'window> {
         // backpack_scene.draw(self);
     }
 }
+
+impl Drop for MainState<'_> {
+    fn drop(&mut self) {
+        // the textures have to be freed while the renderer that created them still exists, the
+        // fields are only dropped after the canvas
+        self.chunks.clear();
+        self.minimap.clear();
+        for atlas in self.atlases.drain(..) {
+            destroy_texture(atlas);
+        }
+    }
+}
+
+// the direction a robot at `from` faces when it moves to `to`, along the longer axis
+fn direction_towards(from: Point, to: Point) -> Option<Direction> {
+    let (dx, dy) = (to.x() - from.x(), to.y() - from.y());
+    if dx == 0 && dy == 0 {
+        None
+    } else if dy.abs() >= dx.abs() {
+        Some(if dy > 0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        })
+    } else {
+        Some(if dx > 0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        })
+    }
+}
+
+// the two strokes of an arrow pointing at `end` along the segment from `start`
+fn arrow_head(start: Point, end: Point) -> [(Point, Point); 2] {
+    let (dx, dy) = ((end.x() - start.x()) as f32, (end.y() - start.y()) as f32);
+    let length = (dx * dx + dy * dy).sqrt();
+    let (ux, uy) = (dx / length, dy / length);
+    let (back, side) = (TILE_SIZE as f32 / 3.0, TILE_SIZE as f32 / 4.0);
+
+    let stroke = |sign: f32| {
+        Point::new(
+            (end.x() as f32 - ux * back - uy * side * sign).round() as i32,
+            (end.y() as f32 - uy * back + ux * side * sign).round() as i32,
+        )
+    };
+    [(stroke(1.0), end), (stroke(-1.0), end)]
+}
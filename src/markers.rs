@@ -1,7 +1,12 @@
 use std::collections::HashMap;
 
-use sdl2::rect::Point;
+use sdl2::pixels::Color;
 
+// markers toggled with the mouse
+pub(crate) const DEFAULT_MARKER_COLOR: Color = Color::RGB(255, 255, 255);
+pub(crate) const DEFAULT_CATEGORY: &str = "default";
+
+// the markers by the (row, col) of their tile, there is at most one per tile
 #[derive(Debug, Clone)]
 pub struct Markers(HashMap<(i32, i32), Marker>);
 
@@ -10,41 +15,196 @@ impl Markers {
         Markers(HashMap::new())
     }
 
-    pub(crate) fn toggle(&mut self, coords: (i32, i32)) -> bool {
+    pub(crate) fn toggle(&mut self, coords: (i32, i32), created_at: u64) -> bool {
         // returns false if toggled off, true if toggled on
         //
         //converts to (y,x) used by the robotics lib
         let coords = (coords.1, coords.0);
-        if let Some(_) = self.0.get(&coords) {
-            self.0.remove(&coords);
+        if self.0.remove(&coords).is_some() {
             false
         } else {
-            self.0.insert(coords, Marker::new(0, 0));
+            self.insert(Marker::new(
+                coords,
+                String::new(),
+                DEFAULT_MARKER_COLOR,
+                DEFAULT_CATEGORY.to_string(),
+                created_at,
+            ));
             true
         }
     }
+    // replaces the marker on the same tile, if any
+    pub(crate) fn insert(&mut self, marker: Marker) {
+        self.0.insert(marker.pos, marker);
+    }
+    pub(crate) fn remove(&mut self, coords: (i32, i32)) -> Option<Marker> {
+        self.0.remove(&coords)
+    }
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+    // oldest first, markers created in the same tick are ordered by position
     pub(crate) fn get_all(&self) -> Vec<((i32, i32), Marker)> {
-        let mut tmp: Vec<((i32, i32), Marker)> = Vec::new();
-        for (coo, mar) in self.0.iter() {
-            tmp.push((coo.clone(), mar.clone()));
-        }
+        self.filtered(|_| true)
+    }
+    pub(crate) fn by_category(&self, category: &str) -> Vec<((i32, i32), Marker)> {
+        self.filtered(|marker| marker.category == category)
+    }
+    // the markers inside the rectangle with the (row, col) corners `min` and `max`, included
+    pub(crate) fn in_region(&self, min: (i32, i32), max: (i32, i32)) -> Vec<((i32, i32), Marker)> {
+        self.filtered(|marker| {
+            (min.0..=max.0).contains(&marker.pos.0) && (min.1..=max.1).contains(&marker.pos.1)
+        })
+    }
+
+    fn filtered(&self, keep: impl Fn(&Marker) -> bool) -> Vec<((i32, i32), Marker)> {
+        let mut tmp = self
+            .0
+            .iter()
+            .filter(|(_, marker)| keep(marker))
+            .map(|(coo, mar)| (*coo, mar.clone()))
+            .collect::<Vec<_>>();
+        tmp.sort_by_key(|(coo, mar)| (mar.created_at, *coo));
         tmp
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Marker {
-    // doesnt make much sense, keeping it anyway
-    pos: Point,
+    // (row, col) of the marked tile
+    pos: (i32, i32),
+    label: String,
+    color: Color,
+    category: String,
+    // fixed steps simulated before the marker was created
+    created_at: u64,
 }
 impl Marker {
-    pub(crate) fn new(x: i32, y: i32) -> Marker {
+    pub(crate) fn new(
+        pos: (i32, i32),
+        label: String,
+        color: Color,
+        category: String,
+        created_at: u64,
+    ) -> Marker {
         Marker {
-            pos: Point::new(x, y),
+            pos,
+            label,
+            color,
+            category,
+            created_at,
         }
     }
     pub fn get_pos(&self) -> (usize, usize) {
         // y,x
-        (self.pos.y as usize, self.pos.x as usize)
+        (self.pos.0 as usize, self.pos.1 as usize)
+    }
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+    pub fn color(&self) -> Color {
+        self.color
+    }
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+    /// Number of fixed steps the GUI had simulated when the marker was created.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(pos: (i32, i32), label: &str, category: &str, created_at: u64) -> Marker {
+        Marker::new(
+            pos,
+            label.to_string(),
+            Color::RGB(10, 20, 30),
+            category.to_string(),
+            created_at,
+        )
+    }
+
+    fn positions(markers: &[((i32, i32), Marker)]) -> Vec<(i32, i32)> {
+        markers.iter().map(|(coords, _)| *coords).collect()
+    }
+
+    #[test]
+    fn regions_include_their_corners() {
+        let mut markers = Markers::new();
+        for row in 0..5 {
+            for col in 0..5 {
+                markers.insert(marker((row, col), "", DEFAULT_CATEGORY, 0));
+            }
+        }
+
+        let inside = markers.in_region((1, 2), (3, 4));
+        assert_eq!(inside.len(), 9);
+        assert!(inside
+            .iter()
+            .all(|((row, col), _)| (1..=3).contains(row) && (2..=4).contains(col)));
+        assert_eq!(positions(&markers.in_region((4, 4), (4, 4))), [(4, 4)]);
+        // corners given the other way around make an empty region
+        assert!(markers.in_region((3, 4), (1, 2)).is_empty());
+    }
+
+    #[test]
+    fn categories_are_matched_exactly() {
+        let mut markers = Markers::new();
+        markers.insert(marker((0, 0), "a", "home", 0));
+        markers.insert(marker((0, 1), "b", "work", 1));
+        markers.insert(marker((0, 2), "c", "home", 2));
+        markers.insert(marker((0, 3), "d", "Home", 3));
+
+        assert_eq!(positions(&markers.by_category("home")), [(0, 0), (0, 2)]);
+        assert_eq!(positions(&markers.by_category("work")), [(0, 1)]);
+        assert!(markers.by_category("hom").is_empty());
+    }
+
+    #[test]
+    fn markers_are_ordered_by_creation_then_position() {
+        let mut markers = Markers::new();
+        markers.insert(marker((5, 5), "", DEFAULT_CATEGORY, 2));
+        markers.insert(marker((3, 9), "", DEFAULT_CATEGORY, 1));
+        markers.insert(marker((3, 1), "", DEFAULT_CATEGORY, 1));
+        markers.insert(marker((0, 0), "", DEFAULT_CATEGORY, 7));
+        markers.insert(marker((1, 8), "", DEFAULT_CATEGORY, 1));
+
+        assert_eq!(
+            positions(&markers.get_all()),
+            [(1, 8), (3, 1), (3, 9), (5, 5), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn a_tile_holds_a_single_marker() {
+        let mut markers = Markers::new();
+        markers.insert(marker((2, 3), "first", "home", 0));
+        markers.insert(marker((2, 3), "second", "work", 4));
+
+        let all = markers.get_all();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].1.label(), "second");
+        assert_eq!(all[0].1.category(), "work");
+        assert_eq!(all[0].1.created_at(), 4);
+
+        // toggling takes (x, y) and removes whatever marker is on the tile
+        assert!(!markers.toggle((3, 2), 5));
+        assert!(markers.get_all().is_empty());
+        // and adds a default one on an empty tile
+        assert!(markers.toggle((3, 2), 6));
+        let all = markers.get_all();
+        assert_eq!(positions(&all), [(2, 3)]);
+        assert_eq!(all[0].1.category(), DEFAULT_CATEGORY);
+        assert_eq!(all[0].1.color(), DEFAULT_MARKER_COLOR);
+        assert_eq!(all[0].1.created_at(), 6);
+
+        // a marker added on a toggled tile replaces it
+        markers.insert(marker((2, 3), "base", "home", 7));
+        assert_eq!(markers.get_all().len(), 1);
+        assert_eq!(markers.get_all()[0].1.label(), "base");
     }
 }
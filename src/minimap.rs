@@ -0,0 +1,325 @@
+use robotics_lib::world::tile::{Tile, TileType};
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::Texture;
+
+use crate::backend::{destroy_texture, RenderCanvas, RenderTextureCreator};
+use crate::camera::Camera;
+use crate::TILE_SIZE;
+
+// the minimap fits in a square of this side, in screen pixels
+const MINIMAP_SIZE: u32 = 200;
+// distance from the bottom right corner of the screen
+const MINIMAP_MARGIN: i32 = 16;
+// side of the dots drawn for robots and markers
+const DOT_SIZE: u32 = 4;
+const UNKNOWN_COLOR: Color = Color::RGBA(24, 26, 34, 220);
+const ROBOT_COLOR: Color = Color::RGB(255, 0, 0);
+const VIEWPORT_COLOR: Color = Color::RGB(255, 255, 255);
+const BORDER_COLOR: Color = Color::RGB(0, 0, 0);
+// past this many changed tiles the whole minimap is redrawn at once
+const MAX_TILE_UPDATES: usize = 256;
+
+// the known map in a corner of the screen, one texel per tile
+pub(crate) struct Minimap {
+    texture: Option<Texture>,
+    // rows and cols of the map the texture has been drawn from
+    size: (usize, usize),
+    // tiles that changed since the texture has been drawn, None if it has to be redrawn
+    changed: Option<Vec<(usize, usize)>>,
+    pub(crate) visible: bool,
+}
+
+impl Minimap {
+    pub(crate) fn new() -> Self {
+        Minimap {
+            texture: None,
+            size: (0, 0),
+            changed: None,
+            visible: false,
+        }
+    }
+
+    pub(crate) fn invalidate(&mut self, row: usize, col: usize) {
+        if let Some(changed) = self.changed.as_mut() {
+            changed.push((row, col));
+            if changed.len() > MAX_TILE_UPDATES {
+                self.changed = None;
+            }
+        }
+    }
+
+    // frees the texture, it is drawn again from the whole map the next time it is shown
+    pub(crate) fn clear(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            destroy_texture(texture);
+        }
+        self.changed = None;
+    }
+
+    // the part of the screen the minimap is drawn on, None while the map is empty
+    pub(crate) fn screen_rect(
+        &self,
+        tiles_world: &[Vec<Option<Tile>>],
+        camera: &Camera,
+    ) -> Option<Rect> {
+        let (rows, cols) = map_size(tiles_world);
+        if rows == 0 || cols == 0 {
+            return None;
+        }
+        // the longer side of the map fills the minimap, the texture is scaled to it by sdl so
+        // on large maps a pixel covers more than one tile
+        let scale = MINIMAP_SIZE as f32 / rows.max(cols) as f32;
+        let width = ((cols as f32 * scale).round() as u32).max(1);
+        let height = ((rows as f32 * scale).round() as u32).max(1);
+
+        let viewport = camera.viewport_rect();
+        Some(Rect::new(
+            viewport.width() as i32 - MINIMAP_MARGIN - width as i32,
+            viewport.height() as i32 - MINIMAP_MARGIN - height as i32,
+            width,
+            height,
+        ))
+    }
+
+    // the (row, col) of the tile drawn at a point of the screen, if it is on the minimap
+    pub(crate) fn tile_at(
+        &self,
+        tiles_world: &[Vec<Option<Tile>>],
+        camera: &Camera,
+        point: Point,
+    ) -> Option<(usize, usize)> {
+        if !self.visible {
+            return None;
+        }
+        let rect = self.screen_rect(tiles_world, camera)?;
+        if !rect.contains_point(point) {
+            return None;
+        }
+        let (rows, cols) = map_size(tiles_world);
+        // the tile under the centre of the pixel, the one sdl samples when scaling the texture
+        let col = (2 * (point.x() - rect.x()) as usize + 1) * cols / (2 * rect.width() as usize);
+        let row = (2 * (point.y() - rect.y()) as usize + 1) * rows / (2 * rect.height() as usize);
+        Some((row.min(rows - 1), col.min(cols - 1)))
+    }
+
+    // `robots` are the positions of the robots in world coordinates, `markers` the (row, col)
+    // and color of the markers
+    pub(crate) fn render(
+        &mut self,
+        canvas: &mut RenderCanvas,
+        texture_creator: &RenderTextureCreator,
+        tiles_world: &[Vec<Option<Tile>>],
+        robots: &[Point],
+        markers: &[((i32, i32), Color)],
+        camera: &Camera,
+    ) -> Result<(), String> {
+        if !self.visible {
+            return Ok(());
+        }
+        let Some(rect) = self.screen_rect(tiles_world, camera) else {
+            return Ok(());
+        };
+        self.update_texture(texture_creator, tiles_world)?;
+        if let Some(texture) = &self.texture {
+            canvas.copy(texture, rect)?;
+        }
+
+        let (rows, cols) = self.size;
+        // world coordinates to minimap coordinates, a tile is centered on (col, row) * TILE_SIZE
+        let to_minimap = |(x, y): (f32, f32)| {
+            Point::new(
+                rect.x()
+                    + ((x / TILE_SIZE as f32 + 0.5) * rect.width() as f32 / cols as f32) as i32,
+                rect.y()
+                    + ((y / TILE_SIZE as f32 + 0.5) * rect.height() as f32 / rows as f32) as i32,
+            )
+        };
+        let dot = |center: Point| Rect::from_center(center, DOT_SIZE, DOT_SIZE);
+
+        for ((row, col), color) in markers {
+            let center = to_minimap(((col * TILE_SIZE) as f32, (row * TILE_SIZE) as f32));
+            canvas.fill_rects(&[dot(center)], *color)?;
+        }
+        let robots = robots
+            .iter()
+            .map(|p| dot(to_minimap((p.x() as f32, p.y() as f32))))
+            .collect::<Vec<_>>();
+        canvas.fill_rects(&robots, ROBOT_COLOR)?;
+
+        let viewport = camera.viewport_rect();
+        let top_left = to_minimap(camera.screen_to_world((0.0, 0.0)));
+        let bottom_right =
+            to_minimap(camera.screen_to_world((viewport.width() as f32, viewport.height() as f32)));
+        let shown = Rect::new(
+            top_left.x(),
+            top_left.y(),
+            (bottom_right.x() - top_left.x()).max(1) as u32,
+            (bottom_right.y() - top_left.y()).max(1) as u32,
+        );
+        if let Some(shown) = shown.intersection(rect) {
+            canvas.draw_rect(shown, VIEWPORT_COLOR)?;
+        }
+        canvas.draw_rect(rect, BORDER_COLOR)
+    }
+
+    // draws the tiles that changed since the last frame on the texture, or all of them if the
+    // map changed size
+    fn update_texture(
+        &mut self,
+        texture_creator: &RenderTextureCreator,
+        tiles_world: &[Vec<Option<Tile>>],
+    ) -> Result<(), String> {
+        let size = map_size(tiles_world);
+        if size != self.size || self.texture.is_none() {
+            let texture = texture_creator.create_texture_streaming(size.1 as u32, size.0 as u32)?;
+            if let Some(old) = self.texture.replace(texture) {
+                destroy_texture(old);
+            }
+            self.size = size;
+            self.changed = None;
+        }
+        let texture = self.texture.as_mut().unwrap();
+
+        match self.changed.take() {
+            Some(changed) => {
+                for (row, col) in changed {
+                    if row >= size.0 || col >= size.1 {
+                        continue;
+                    }
+                    let tile = tiles_world
+                        .get(row)
+                        .and_then(|r| r.get(col))
+                        .and_then(|t| t.as_ref());
+                    let c = tile_color(tile);
+                    texture
+                        .update(
+                            Rect::new(col as i32, row as i32, 1, 1),
+                            &[c.r, c.g, c.b, c.a],
+                            4,
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            None => {
+                let mut pixels = Vec::with_capacity(size.0 * size.1 * 4);
+                for tiles in tiles_world {
+                    for col in 0..size.1 {
+                        let tile = tiles.get(col).and_then(|t| t.as_ref());
+                        let c = tile_color(tile);
+                        pixels.extend_from_slice(&[c.r, c.g, c.b, c.a]);
+                    }
+                }
+                texture
+                    .update(None, &pixels, size.1 * 4)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        self.changed = Some(Vec::new());
+        Ok(())
+    }
+}
+
+impl Drop for Minimap {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// rows and cols of the map, rows can be shorter than the longest one
+fn map_size(tiles_world: &[Vec<Option<Tile>>]) -> (usize, usize) {
+    let cols = tiles_world.iter().map(|r| r.len()).max().unwrap_or(0);
+    (tiles_world.len(), cols)
+}
+
+fn tile_color(tile: Option<&Tile>) -> Color {
+    let Some(tile) = tile else {
+        return UNKNOWN_COLOR;
+    };
+    match tile.tile_type {
+        TileType::DeepWater => Color::RGB(16, 46, 130),
+        TileType::ShallowWater => Color::RGB(64, 150, 220),
+        TileType::Sand => Color::RGB(230, 205, 150),
+        TileType::Grass => Color::RGB(90, 170, 40),
+        TileType::Street => Color::RGB(130, 130, 130),
+        TileType::Hill => Color::RGB(140, 115, 75),
+        TileType::Mountain => Color::RGB(95, 80, 70),
+        TileType::Snow => Color::RGB(245, 245, 250),
+        TileType::Lava => Color::RGB(220, 60, 20),
+        TileType::Teleport(_) => Color::RGB(170, 60, 230),
+        TileType::Wall => Color::RGB(50, 45, 45),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::grass_map as map;
+
+    const VIEWPORT: (u32, u32) = (1280, 720);
+
+    fn visible_minimap() -> Minimap {
+        let mut minimap = Minimap::new();
+        minimap.visible = true;
+        minimap
+    }
+
+    #[test]
+    fn the_longer_side_fills_the_minimap() {
+        let minimap = visible_minimap();
+        let camera = Camera::new(VIEWPORT);
+
+        // 5 pixels per tile, in the bottom right corner
+        let wide = minimap.screen_rect(&map(10, 40), &camera).unwrap();
+        assert_eq!(wide, Rect::new(1064, 654, 200, 50));
+        let tall = minimap.screen_rect(&map(40, 10), &camera).unwrap();
+        assert_eq!(tall, Rect::new(1214, 504, 50, 200));
+        // large maps are scaled down to fit as well
+        let huge = minimap.screen_rect(&map(300, 50), &camera).unwrap();
+        assert_eq!(huge, Rect::new(1231, 504, 33, 200));
+        let line = minimap.screen_rect(&map(1, 1000), &camera).unwrap();
+        assert_eq!((line.width(), line.height()), (200, 1));
+
+        assert_eq!(minimap.screen_rect(&map(0, 0), &camera), None);
+    }
+
+    #[test]
+    fn tile_at_matches_the_drawn_tiles() {
+        let mut minimap = visible_minimap();
+        let camera = Camera::new(VIEWPORT);
+
+        for (rows, cols) in [(10, 40), (40, 10), (300, 50)] {
+            let tiles = map(rows, cols);
+            let rect = minimap.screen_rect(&tiles, &camera).unwrap();
+            let at = |x: i32, y: i32| minimap.tile_at(&tiles, &camera, Point::new(x, y));
+            assert_eq!(at(rect.x(), rect.y()), Some((0, 0)));
+            assert_eq!(
+                at(rect.right() - 1, rect.bottom() - 1),
+                Some((rows - 1, cols - 1))
+            );
+            // moving right or down never goes back to an earlier tile
+            let cols_along: Vec<_> = (rect.x()..rect.right())
+                .map(|x| at(x, rect.y()).unwrap().1)
+                .collect();
+            assert!(cols_along.windows(2).all(|pair| pair[0] <= pair[1]));
+            let rows_along: Vec<_> = (rect.y()..rect.bottom())
+                .map(|y| at(rect.x(), y).unwrap().0)
+                .collect();
+            assert!(rows_along.windows(2).all(|pair| pair[0] <= pair[1]));
+
+            assert_eq!(at(rect.x() - 1, rect.y()), None);
+            assert_eq!(at(rect.x(), rect.bottom()), None);
+        }
+
+        // 5 pixels per tile, the last pixel of a tile and the first one of the next
+        let tiles = map(10, 40);
+        let rect = minimap.screen_rect(&tiles, &camera).unwrap();
+        let at = |x: i32, y: i32| minimap.tile_at(&tiles, &camera, Point::new(x, y));
+        let (x, y) = (rect.x() + 15, rect.y() + 35);
+        assert_eq!(at(x - 1, y - 1), Some((6, 2)));
+        assert_eq!(at(x, y), Some((7, 3)));
+
+        minimap.visible = false;
+        assert_eq!(minimap.tile_at(&tiles, &camera, rect.center()), None);
+    }
+}
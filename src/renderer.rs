@@ -1,56 +1,58 @@
-use crate::components::drawable_components::{Position, Sprite};
+use crate::components::drawable_components::{Position, Sprite, Tint};
 use crate::texture_manager::TextureType;
 use crate::{Camera, HEIGHT, TILE_SIZE, WIDTH};
 
+use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{Texture, WindowCanvas};
+use sdl2::render::{Canvas, RenderTarget, Texture};
 use specs::prelude::*;
 use specs::ReadStorage;
 
 //this Extracts data from every entity that has a Position ans Sprite component
-type SystemData<'a> = (ReadStorage<'a, Position>, ReadStorage<'a, Sprite>);
+type SystemData<'a> = (
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Sprite>,
+    ReadStorage<'a, Tint>,
+);
+// width in screen pixels of the lines, odd so that they are centered on their points
+const LINE_WIDTH: i32 = 3;
 
-pub(crate) fn render_sprites(
-    canvas: &mut WindowCanvas,
-    texture: &mut Texture,
+// the n-th atlas draws the sprites with atlas id n, everything is drawn in the order of the
+// entities so that squares and sprites of the same world can be stacked, consecutive sprites of
+// one atlas are copied together until a sprite of another atlas, a square or a line comes up
+pub(crate) fn render_sprites<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    atlases: &mut [Texture],
     data: SystemData,
-    camera: &mut Camera,
+    camera: &Camera,
 ) -> Result<(), String> {
-    for (pos, sprite) in (&data.0, &data.1).join() {
-        //TODO: add check if the compomentent to be rendered is inside the viewport
-        let (window_width, window_height) = canvas.output_size().unwrap();
-
-        if camera.chase_robot && sprite.texture_type == TextureType::Robot {
-            camera.robot_position = pos.0;
-            camera.screen_offset = (window_width as i32 / 2, window_height as i32 / 2);
-        }
+    let viewport = camera.viewport_rect();
+    let mut batch = SpriteBatch::default();
 
+    for (pos, sprite, tint) in (&data.0, &data.1, data.2.maybe()).join() {
+        let tint = tint.map(|t| t.0);
         match sprite.texture_type {
             TextureType::Time(_) | TextureType::EnvCondition(_) => {
-                canvas.copy(&texture, sprite.region, None)?
+                batch.push(canvas, atlases, sprite, None, tint)?
             }
             TextureType::FontCharater(_, scale, fixed) | TextureType::Item(_, scale, fixed) => {
-                let screen_rect;
-                if fixed {
-                    screen_rect = Rect::from_center(
-                        pos.0,
-                        (sprite.region.width() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
-                            as u32,
-                        (sprite.region.height() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
-                            as u32,
-                    );
+                let screen_position = if fixed {
+                    pos.0
                 } else {
-                    let screen_position = calculate_screen_position(pos.0, camera, canvas);
-                    screen_rect = Rect::from_center(
-                        screen_position,
-                        (sprite.region.width() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
-                            as u32,
-                        (sprite.region.height() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
-                            as u32,
-                    );
+                    camera.world_point_to_screen(pos.0)
+                };
+                let screen_rect = Rect::from_center(
+                    screen_position,
+                    (sprite.region.width() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
+                        as u32,
+                    (sprite.region.height() as i32 + (TILE_SIZE as f32 * (scale - 1.0)) as i32)
+                        as u32,
+                );
+                if !fixed && !screen_rect.has_intersection(viewport) {
+                    continue;
                 }
 
-                canvas.copy(&texture, sprite.region, screen_rect)?;
+                batch.push(canvas, atlases, sprite, Some(screen_rect), tint)?;
             }
             TextureType::Square(size, color, centered, fixed) => {
                 let screen_rect;
@@ -65,108 +67,117 @@ pub(crate) fn render_sprites(
                         screen_rect = Rect::from_center(pos.0, size.0, size.1);
                     }
                 } else {
-                    let screen_position = calculate_screen_position(pos.0, camera, canvas);
-                    screen_rect = Rect::from_center(screen_position, size.0, size.1);
+                    screen_rect =
+                        Rect::from_center(camera.world_point_to_screen(pos.0), size.0, size.1);
+                    if !screen_rect.has_intersection(viewport) {
+                        continue;
+                    }
                 }
+                batch.flush(canvas, atlases)?;
                 canvas.set_draw_color(color);
                 canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
                 canvas.fill_rect(screen_rect)?;
                 canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
             }
-            _ => {
-                //this rappresents the point in the canvas where the sprite will be placed
-                let screen_position = calculate_screen_position(pos.0, camera, canvas);
-
-                let scaled_width = sprite.region.width() as i32 + camera.zoom_level;
-                let scaled_height = sprite.region.height() as i32 + camera.zoom_level;
+            TextureType::Line(end, color) => {
+                let start = camera.world_point_to_screen(pos.0);
+                let end = camera.world_point_to_screen(end);
+                // the line is made LINE_WIDTH pixels wide by drawing it again next to itself
+                let horizontal = (end.x() - start.x()).abs() >= (end.y() - start.y()).abs();
 
+                batch.flush(canvas, atlases)?;
+                canvas.set_draw_color(color);
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for offset in -LINE_WIDTH / 2..=LINE_WIDTH / 2 {
+                    let shift = if horizontal {
+                        Point::new(0, offset)
+                    } else {
+                        Point::new(offset, 0)
+                    };
+                    canvas.draw_line(start + shift, end + shift)?;
+                }
+                canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
+            }
+            _ => {
                 //this represents the area of the screen on which the sprite region will be placed to.
-                let screen_rect =
-                    Rect::from_center(screen_position, scaled_width as u32, scaled_height as u32);
+                let screen_rect = camera.world_rect_to_screen(
+                    pos.0,
+                    sprite.region.width(),
+                    sprite.region.height(),
+                );
+                // sprites that are out of the screen are skipped
+                if !screen_rect.has_intersection(viewport) {
+                    continue;
+                }
 
-                canvas.copy(&texture, sprite.region, screen_rect)?;
+                batch.push(canvas, atlases, sprite, Some(screen_rect), tint)?;
             }
         }
     }
 
-    Ok(())
+    batch.flush(canvas, atlases)
 }
-pub(crate) fn calculate_screen_position(
-    component_pos: Point,
-    camera: &Camera,
-    canvas: &WindowCanvas,
-) -> Point {
-    //TODO: add camera following
-    let (window_width, window_height) = canvas.output_size().unwrap();
-    let screen_position = component_pos; // Point::new(window_width as i32 / 2, window_height as i32 / 2);
 
-    if camera.chase_robot {
-        //doesn't work properly
-        component_pos - Point::new(camera.robot_position.x(), camera.robot_position.y())
-            + Point::new(camera.screen_offset.0, camera.screen_offset.1) //mouse mov
-            + Point::new( camera.zoom_level
-                    * (screen_position.x())
-                    / TILE_SIZE,
-                camera.zoom_level
-                    * (screen_position.y())
-                    / TILE_SIZE,
-            )
-    } else {
-        component_pos //+ Point::new(camera.screen_offset.0, camera.screen_offset.1) //mouse mov
-        + Point::new( camera.screen_offset.0 +
-        camera.zoom_level * (screen_position.x()- ((window_width as i32/2)- camera.screen_offset.0))/ TILE_SIZE ,
-        camera.screen_offset.1 +
-        camera.zoom_level * (screen_position.y()- ((window_height as i32/2) - camera.screen_offset.1))/ TILE_SIZE ,
-        )
-    }
+// consecutive sprites of one atlas with the same tint, not copied yet
+#[derive(Default)]
+struct SpriteBatch {
+    atlas: usize,
+    tint: Option<Color>,
+    // the regions of the atlas and where they go, None stretches a region over the whole screen
+    copies: Vec<(Rect, Option<Rect>)>,
 }
-pub(crate) fn calculate_map_coords(
-    screen_position: Point,
-    camera: &Camera,
-    canvas: &WindowCanvas,
-) -> Point {
-    let tmp;
-    let (window_width, window_height) = canvas.output_size().unwrap();
 
-    if camera.chase_robot {
-        tmp = Point::new(
-            (TILE_SIZE
-                * (screen_position.x() - camera.screen_offset.0 + camera.robot_position.x())
-                + camera.zoom_level
-                    * (window_width as i32 / 2 - camera.screen_offset.0
-                        + camera.robot_position.x()))
-                / (camera.zoom_level + TILE_SIZE),
-            (TILE_SIZE
-                * (screen_position.y() - camera.screen_offset.1 + camera.robot_position.x())
-                + camera.zoom_level
-                    * (window_height as i32 / 2 - camera.screen_offset.1
-                        + camera.robot_position.y()))
-                / (camera.zoom_level + TILE_SIZE),
-        ) + Point::new(
-            (TILE_SIZE + camera.zoom_level) / 2,
-            (TILE_SIZE + camera.zoom_level) / 2,
-        )
-    } else {
-        tmp = Point::new(
-            (TILE_SIZE * (screen_position.x() - camera.screen_offset.0)
-                + camera.zoom_level * (window_width as i32 / 2 - camera.screen_offset.0))
-                / (camera.zoom_level + TILE_SIZE),
-            (TILE_SIZE * (screen_position.y() - camera.screen_offset.1)
-                + camera.zoom_level * (window_height as i32 / 2 - camera.screen_offset.1))
-                / (camera.zoom_level + TILE_SIZE),
-        ) + Point::new(
-            (TILE_SIZE + camera.zoom_level) / 2,
-            (TILE_SIZE + camera.zoom_level) / 2,
-        );
+impl SpriteBatch {
+    // adds a sprite, the sprites before it are copied first if it needs another atlas or tint
+    fn push<T: RenderTarget>(
+        &mut self,
+        canvas: &mut Canvas<T>,
+        atlases: &mut [Texture],
+        sprite: &Sprite,
+        dst: Option<Rect>,
+        tint: Option<Color>,
+    ) -> Result<(), String> {
+        if sprite.atlas != self.atlas || tint != self.tint {
+            self.flush(canvas, atlases)?;
+            self.atlas = sprite.atlas;
+            self.tint = tint;
+        }
+        self.copies.push((sprite.region, dst));
+        Ok(())
+    }
+
+    // copies the pending sprites, the ones of an atlas that is not loaded are skipped
+    fn flush<T: RenderTarget>(
+        &mut self,
+        canvas: &mut Canvas<T>,
+        atlases: &mut [Texture],
+    ) -> Result<(), String> {
+        let copies = std::mem::take(&mut self.copies);
+        let Some(atlas) = atlases.get_mut(self.atlas).filter(|_| !copies.is_empty()) else {
+            return Ok(());
+        };
+        // the color mod belongs to the whole atlas, so it is reset after every tinted batch
+        if let Some(tint) = self.tint {
+            atlas.set_color_mod(tint.r, tint.g, tint.b);
+        }
+        for (src, dst) in copies {
+            canvas.copy(atlas, src, dst)?;
+        }
+        if self.tint.is_some() {
+            atlas.set_color_mod(255, 255, 255);
+        }
+        Ok(())
     }
-    Point::new(tmp.x / (TILE_SIZE), tmp.y / (TILE_SIZE))
-    //tmp
 }
 
 #[derive(Clone, Eq, Hash, PartialEq, Debug)]
 pub(crate) enum Layer {
     Tiles,
     Content,
+    // drawn from the known map instead of a world
+    Fog,
+    Trail,
+    Path,
     Robot,
     Weather,
     OverlayHint,
@@ -174,9 +185,12 @@ pub(crate) enum Layer {
     Time,
     Ui(String, u32, u32), // layer, sublayer
 }
-pub(crate) const RENDER_ORDER: [Layer; 7] = [
+pub(crate) const RENDER_ORDER: [Layer; 10] = [
     Layer::Tiles,
     Layer::Content,
+    Layer::Fog,
+    Layer::Trail,
+    Layer::Path,
     Layer::Robot,
     Layer::Weather,
     Layer::OverlayHint,
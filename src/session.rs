@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::Path;
+
+use sdl2::pixels::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::markers::Marker;
+
+// written in every session file, files with a different version are refused
+const SESSION_VERSION: u32 = 1;
+
+// layout of the files written by MainState::save_session, as pretty printed json so that they
+// can be read and edited by hand
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Session {
+    version: u32,
+    pub(crate) camera: CameraState,
+    // names of the open menus, e.g. "markers"
+    #[serde(default)]
+    pub(crate) open_menus: Vec<String>,
+    #[serde(default)]
+    pub(crate) markers: Vec<MarkerState>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CameraState {
+    pub(crate) pan: (f32, f32),
+    pub(crate) scale: f32,
+    #[serde(default)]
+    pub(crate) chase_robot: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MarkerState {
+    row: i32,
+    col: i32,
+    #[serde(default)]
+    label: String,
+    // [r, g, b, a]
+    color: [u8; 4],
+    category: String,
+    #[serde(default)]
+    created_at: u64,
+}
+
+impl Session {
+    pub(crate) fn new(
+        camera: CameraState,
+        open_menus: Vec<String>,
+        markers: Vec<MarkerState>,
+    ) -> Self {
+        Session {
+            version: SESSION_VERSION,
+            camera,
+            open_menus,
+            markers,
+        }
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<Session, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let session: Session =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if session.version != SESSION_VERSION {
+            return Err(format!(
+                "{}: session version {} is not supported, expected {}",
+                path.display(),
+                session.version,
+                SESSION_VERSION
+            ));
+        }
+        Ok(session)
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl MarkerState {
+    pub(crate) fn new((row, col): (i32, i32), marker: &Marker) -> Self {
+        let color = marker.color();
+        MarkerState {
+            row,
+            col,
+            label: marker.label().to_string(),
+            color: [color.r, color.g, color.b, color.a],
+            category: marker.category().to_string(),
+            created_at: marker.created_at(),
+        }
+    }
+
+    pub(crate) fn into_marker(self) -> Marker {
+        let [r, g, b, a] = self.color;
+        Marker::new(
+            (self.row, self.col),
+            self.label,
+            Color::RGBA(r, g, b, a),
+            self.category,
+            self.created_at,
+        )
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::animation::Animation;
+use crate::components::drawable_components::{Animated, Sprite};
+use crate::components::movement_components::Velocity;
+use crate::components::robot_components::Robot;
+use crate::texture_manager::{Facing, SpriteKey, SpriteRegion, SpriteTable, TextureType, FACINGS};
+
+// time the animations have been running for, advanced by one fixed step at a time
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct AnimationClock(pub(crate) Duration);
+
+// the sprites the robot switches between while it moves
+pub(crate) struct RobotSprites {
+    idle: Option<SpriteRegion>,
+    facing: HashMap<Facing, SpriteRegion>,
+    walking: HashMap<Facing, Animation>,
+}
+
+impl RobotSprites {
+    pub(crate) fn new(sprite_table: &SpriteTable) -> Self {
+        let facing = FACINGS
+            .into_iter()
+            .filter_map(|facing| {
+                sprite_table
+                    .sprite(&SpriteKey::RobotFacing(facing))
+                    .map(|region| (facing, region))
+            })
+            .collect();
+        let walking = FACINGS
+            .into_iter()
+            .filter_map(|facing| {
+                sprite_table
+                    .animation(&SpriteKey::RobotWalking(facing))
+                    .map(|animation| (facing, animation.clone()))
+            })
+            .collect();
+
+        RobotSprites {
+            idle: sprite_table.region(&TextureType::Robot),
+            facing,
+            walking,
+        }
+    }
+}
+
+pub(crate) struct AnimationSystem;
+
+impl<'a> System<'a> for AnimationSystem {
+    type SystemData = (
+        ReadStorage<'a, Animated>,
+        WriteStorage<'a, Sprite>,
+        ReadExpect<'a, AnimationClock>,
+    );
+
+    fn run(&mut self, (animated, mut sprites, clock): Self::SystemData) {
+        for (animated, sprite) in (&animated, &mut sprites).join() {
+            let frame = animated
+                .animation
+                .frame(clock.0.saturating_sub(animated.start));
+            sprite.region = frame.rect;
+            sprite.atlas = frame.atlas;
+        }
+    }
+}
+
+// shows the robot looking towards the direction it is moving in, with its walking animation if
+// it has one, and goes back to the idle sprite once it stops
+pub(crate) struct RobotSpriteSystem;
+
+impl<'a> System<'a> for RobotSpriteSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Robot>,
+        WriteStorage<'a, Animated>,
+        WriteStorage<'a, Sprite>,
+        ReadExpect<'a, RobotSprites>,
+        ReadExpect<'a, AnimationClock>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, vel, robots, mut animated, mut sprites, robot_sprites, clock): Self::SystemData,
+    ) {
+        for (entity, vel, robot, sprite) in (&entities, &vel, &robots, &mut sprites).join() {
+            // a robot with its own skin always looks the same
+            if let Some(skin) = robot.skin {
+                animated.remove(entity);
+                sprite.region = skin.rect;
+                sprite.atlas = skin.atlas;
+                continue;
+            }
+
+            let facing = vel.direction.as_ref().map(Facing::from);
+
+            match facing.and_then(|facing| robot_sprites.walking.get(&facing)) {
+                Some(animation) => {
+                    let playing = animated.get(entity).map(|a| &a.animation);
+                    if playing != Some(animation) {
+                        let _ = animated.insert(
+                            entity,
+                            Animated {
+                                animation: animation.clone(),
+                                start: clock.0,
+                            },
+                        );
+                    }
+                }
+                None => {
+                    animated.remove(entity);
+
+                    let still = facing
+                        .and_then(|facing| robot_sprites.facing.get(&facing).copied())
+                        .or(robot_sprites.idle);
+                    if let Some(region) = still {
+                        sprite.region = region.rect;
+                        sprite.atlas = region.atlas;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use specs::{Join, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::camera::Camera;
+use crate::components::drawable_components::Position;
+use crate::components::robot_components::{Robot, RobotId};
+
+// the robot the camera keeps track of, None while no robot has been added
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ChasedRobot(pub(crate) Option<RobotId>);
+
+pub(crate) struct CameraFollowSystem;
+
+impl<'a> System<'a> for CameraFollowSystem {
+    type SystemData = (
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Robot>,
+        ReadExpect<'a, ChasedRobot>,
+        WriteExpect<'a, Camera>,
+    );
+
+    fn run(&mut self, (pos, robots, chased, mut camera): Self::SystemData) {
+        let chased = (&pos, &robots)
+            .join()
+            .find(|(_, robot)| Some(robot.id) == chased.0);
+        if let Some((pos, _)) = chased {
+            camera.robot_position = pos.0;
+
+            if camera.chase_robot {
+                camera.follow((pos.0.x() as f32, pos.0.y() as f32));
+            }
+        }
+    }
+}
@@ -1 +1,3 @@
+pub mod animation_systems;
+pub mod camera_systems;
 pub mod movement_systems;
@@ -1,47 +1,71 @@
-use robotics_lib::interface::Direction;
-use specs::{Join, ReadExpect, ReadStorage, System, WriteStorage};
+use sdl2::rect::Point;
+use specs::{Entities, Join, ReadStorage, System, WriteStorage};
 
-use crate::components::drawable_components::Position;
+use crate::components::drawable_components::{Attached, Position};
 use crate::components::movement_components::Velocity;
+use crate::components::robot_components::Robot;
 
 pub(crate) struct MoveSystem;
 
 impl<'a> System<'a> for MoveSystem {
     //there are the resources requires for the execution of the system
-    type SystemData = (WriteStorage<'a, Position>, ReadStorage<'a, Velocity>);
-
-    fn run(&mut self, (mut pos, vel): Self::SystemData) {
-        //this system will only work with
-        //entitis that have both a Position and a Velocity components
+    type SystemData = (WriteStorage<'a, Position>, ReadStorage<'a, Robot>);
 
+    fn run(&mut self, (mut pos, robots): Self::SystemData) {
         //the join combines multiple component storages, so we access only entities that use both
         //of them
 
-        for (pos, vel) in (&mut pos, &vel).join() {
-            match &vel.direction {
-                Some(d) => match d {
-                    robotics_lib::interface::Direction::Up => pos.0.y -= vel.speed,
-                    robotics_lib::interface::Direction::Down => pos.0.y += vel.speed,
-                    robotics_lib::interface::Direction::Left => pos.0.x -= vel.speed,
-                    robotics_lib::interface::Direction::Right => pos.0.x += vel.speed,
-                },
-                None => return,
-            }
+        for (pos, robot) in (&mut pos, &robots).join() {
+            // covers an equal share of what is left to the target in each remaining step, so
+            // the robot ends up exactly on its tile wherever it has been drawn before
+            let left = robot.target - pos.0;
+            let steps = robot.remaining_steps as i32 + 1;
+            pos.0 += Point::new(left.x() / steps, left.y() / steps);
         }
     }
 }
 
+// moves every robot towards the tile given to its last update_robot until it gets there
 pub(crate) struct ChangeDirectionSystem;
 impl<'a> System<'a> for ChangeDirectionSystem {
+    type SystemData = (WriteStorage<'a, Robot>, WriteStorage<'a, Velocity>);
+
+    fn run(&mut self, (mut robots, mut vel): Self::SystemData) {
+        for (robot, vel) in (&mut robots, &mut vel).join() {
+            if robot.remaining_steps == 0 {
+                // the robot reached its tile, it stays there until the next update_robot
+                vel.direction = None;
+            } else {
+                robot.remaining_steps -= 1;
+                vel.direction = robot.direction.clone();
+            }
+        }
+    }
+}
+
+// moves the entities attached to another one along with it
+pub(crate) struct AttachSystem;
+
+impl<'a> System<'a> for AttachSystem {
     type SystemData = (
-        ReadExpect<'a, Option<Option<Direction>>>,
-        WriteStorage<'a, Velocity>,
+        Entities<'a>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, Attached>,
     );
 
-    fn run(&mut self, mut data: Self::SystemData) {
-        let dir = &*data.0;
-        for vel in (&mut data.1).join() {
-            vel.direction = dir.clone().unwrap()
+    fn run(&mut self, (entities, mut pos, attached): Self::SystemData) {
+        let moved = (&entities, &attached)
+            .join()
+            .filter_map(|(entity, attached)| {
+                pos.get(attached.parent)
+                    .map(|parent| (entity, parent.0 + attached.offset))
+            })
+            .collect::<Vec<_>>();
+
+        for (entity, point) in moved {
+            if let Some(pos) = pos.get_mut(entity) {
+                pos.0 = point;
+            }
         }
     }
 }
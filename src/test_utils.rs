@@ -0,0 +1,11 @@
+use robotics_lib::world::tile::{Content, Tile, TileType};
+
+// a map of grass tiles, shared by the unit tests
+pub(crate) fn grass_map(rows: usize, cols: usize) -> Vec<Vec<Option<Tile>>> {
+    let tile = Tile {
+        tile_type: TileType::Grass,
+        content: Content::None,
+        elevation: 0,
+    };
+    vec![vec![Some(tile); cols]; rows]
+}
@@ -1,30 +1,127 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+use robotics_lib::interface::Direction;
 use robotics_lib::world::{
     environmental_conditions::{DayTime, WeatherType},
     tile::{Content, TileType},
 };
-use sdl2::{pixels::Color, rect::Rect};
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+};
+use serde::Deserialize;
 
+use crate::animation::{Animation, LoopMode};
 use crate::TILE_SIZE;
 
 const FONT_STRING: &str =
     "!#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz{|}~ \"";
 
+// index of a texture in MainState::atlases, the default atlas is always the first one
+pub(crate) type AtlasId = usize;
+pub(crate) const DEFAULT_ATLAS_ID: AtlasId = 0;
+
+// where a sprite is drawn from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SpriteRegion {
+    pub(crate) atlas: AtlasId,
+    pub(crate) rect: Rect,
+}
+
 #[derive(Debug)]
-pub(crate) struct SpriteTable(pub HashMap<TextureType, Rect>);
+pub(crate) struct SpriteTable {
+    sprites: HashMap<SpriteKey, SpriteRegion>,
+    // alternative content sprites with the quantity they are used from, sorted by quantity
+    content_levels: HashMap<ContentKind, Vec<(usize, SpriteRegion)>>,
+    // sprites that are drawn with a sequence of frames instead of their region
+    animations: HashMap<SpriteKey, Animation>,
+}
 impl SpriteTable {
     pub fn new() -> Self {
-        SpriteTable(HashMap::new())
+        SpriteTable {
+            sprites: HashMap::new(),
+            content_levels: HashMap::new(),
+            animations: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn sprite(&self, key: &SpriteKey) -> Option<SpriteRegion> {
+        self.sprites.get(key).copied()
+    }
+
+    pub(crate) fn animation(&self, key: &SpriteKey) -> Option<&Animation> {
+        self.animations.get(key)
+    }
+    // the animation of a texture type, content with quantity levels is never animated
+    pub(crate) fn texture_animation(&self, texture_type: &TextureType) -> Option<&Animation> {
+        if let TextureType::Content(content) = texture_type {
+            if self
+                .content_levels
+                .contains_key(&ContentKind::from(content))
+            {
+                return None;
+            }
+        }
+        self.animation(&texture_type.sprite_key()?)
+    }
+    // the tile sprites whose animation shows a different frame at `now` than at `before`
+    pub(crate) fn changed_tile_frames(&self, before: Duration, now: Duration) -> Vec<SpriteKey> {
+        self.animations
+            .iter()
+            .filter(|(key, _)| matches!(key, SpriteKey::Tile(_)))
+            .filter(|(_, animation)| animation.frame(before) != animation.frame(now))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+    // the region a texture type is drawn from at the given time of the animation clock
+    pub(crate) fn region_at(
+        &self,
+        texture_type: &TextureType,
+        time: Duration,
+    ) -> Option<SpriteRegion> {
+        match self.texture_animation(texture_type) {
+            Some(animation) => Some(animation.frame(time)),
+            None => self.region(texture_type),
+        }
+    }
+
+    // the region a texture type is drawn from, content uses the sprite of the highest level its
+    // quantity reaches
+    pub(crate) fn region(&self, texture_type: &TextureType) -> Option<SpriteRegion> {
+        match texture_type {
+            TextureType::Content(content) => {
+                let kind = ContentKind::from(content);
+                let quantity = content_quantity(content);
+                self.content_levels
+                    .get(&kind)
+                    .and_then(|levels| levels.iter().rev().find(|(min, _)| quantity >= *min))
+                    .map(|(_, region)| *region)
+                    .or_else(|| self.sprites.get(&SpriteKey::Content(kind)).copied())
+            }
+            TextureType::Item(item, _, _) => self.region(item),
+            texture_type => self.sprites.get(&texture_type.sprite_key()?).copied(),
+        }
     }
     pub fn load_default_sprites(&mut self) {
-        //TODO: add parsing from json???
-        self.0.insert(
-            TextureType::Robot,
-            Rect::new(TILE_SIZE * 2, 0, TILE_SIZE as u32, TILE_SIZE as u32),
+        self.load_sprite(
+            SpriteKey::Robot,
+            Rect::new(0, TILE_SIZE * 2, TILE_SIZE as u32, TILE_SIZE as u32),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Grass),
+        // the robot while it moves, looking up, down, left and right
+        for (col, facing) in (8..).zip(FACINGS) {
+            self.load_sprite(
+                SpriteKey::RobotFacing(facing),
+                Rect::new(TILE_SIZE * col, 0, TILE_SIZE as u32, TILE_SIZE as u32),
+            );
+        }
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Grass),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 0,
@@ -33,8 +130,8 @@ impl SpriteTable {
             ),
         );
 
-        self.0.insert(
-            TextureType::Tile(TileType::Sand),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Sand),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 0,
@@ -42,8 +139,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::None),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::None),
             Rect::new(
                 TILE_SIZE * 1,
                 TILE_SIZE * 4,
@@ -51,8 +148,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Rock(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Rock),
             Rect::new(
                 TILE_SIZE * 2,
                 TILE_SIZE * 0,
@@ -60,8 +157,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Garbage(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Garbage),
             Rect::new(
                 TILE_SIZE * 4,
                 TILE_SIZE * 2,
@@ -69,8 +166,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Fire),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Fire),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 2,
@@ -78,8 +175,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Coin(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Coin),
             Rect::new(
                 TILE_SIZE * 6,
                 TILE_SIZE * 2,
@@ -87,8 +184,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Bin(0..0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Bin),
             Rect::new(
                 TILE_SIZE * 7,
                 TILE_SIZE * 2,
@@ -96,8 +193,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Crate(0..0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Crate),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 3,
@@ -105,8 +202,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Bank(0..0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Bank),
             Rect::new(
                 TILE_SIZE * 1,
                 TILE_SIZE * 3,
@@ -114,8 +211,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Market(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Market),
             Rect::new(
                 TILE_SIZE * 2,
                 TILE_SIZE * 3,
@@ -123,8 +220,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Water(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Water),
             Rect::new(
                 TILE_SIZE * 3,
                 TILE_SIZE * 3,
@@ -132,8 +229,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Fish(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Fish),
             Rect::new(
                 TILE_SIZE * 4,
                 TILE_SIZE * 3,
@@ -141,8 +238,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Building),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Building),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 3,
@@ -150,8 +247,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Bush(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Bush),
             Rect::new(
                 TILE_SIZE * 6,
                 TILE_SIZE * 3,
@@ -159,8 +256,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::JollyBlock(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::JollyBlock),
             Rect::new(
                 TILE_SIZE * 7,
                 TILE_SIZE * 3,
@@ -168,8 +265,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Scarecrow),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Scarecrow),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 4,
@@ -177,8 +274,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Content(Content::Tree(0)),
+        self.load_sprite(
+            SpriteKey::Content(ContentKind::Tree),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 1,
@@ -187,8 +284,8 @@ impl SpriteTable {
             ),
         );
 
-        self.0.insert(
-            TextureType::Tile(TileType::Street),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Street),
             Rect::new(
                 TILE_SIZE * 1,
                 TILE_SIZE * 0,
@@ -196,8 +293,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::ShallowWater),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::ShallowWater),
             Rect::new(
                 TILE_SIZE * 6,
                 TILE_SIZE * 0,
@@ -205,8 +302,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::DeepWater),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::DeepWater),
             Rect::new(
                 TILE_SIZE * 7,
                 TILE_SIZE * 0,
@@ -215,8 +312,8 @@ impl SpriteTable {
             ),
         );
 
-        self.0.insert(
-            TextureType::Tile(TileType::Teleport(false)),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Teleport(false)),
             Rect::new(
                 TILE_SIZE * 2,
                 TILE_SIZE * 1,
@@ -224,8 +321,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Teleport(true)),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Teleport(true)),
             Rect::new(
                 TILE_SIZE * 2,
                 TILE_SIZE * 2,
@@ -234,8 +331,8 @@ impl SpriteTable {
             ),
         );
 
-        self.0.insert(
-            TextureType::Tile(TileType::Wall),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Wall),
             Rect::new(
                 TILE_SIZE * 3,
                 TILE_SIZE * 1,
@@ -243,8 +340,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Mountain),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Mountain),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 1,
@@ -252,8 +349,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Snow),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Snow),
             Rect::new(
                 TILE_SIZE * 6,
                 TILE_SIZE * 1,
@@ -261,8 +358,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Lava),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Lava),
             Rect::new(
                 TILE_SIZE * 7,
                 TILE_SIZE * 1,
@@ -270,8 +367,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Tile(TileType::Hill),
+        self.load_sprite(
+            SpriteKey::Tile(TileType::Hill),
             Rect::new(
                 TILE_SIZE * 4,
                 TILE_SIZE * 1,
@@ -280,8 +377,8 @@ impl SpriteTable {
             ),
         );
 
-        self.0.insert(
-            TextureType::Overlay(OverlayType::TileHover),
+        self.load_sprite(
+            SpriteKey::Overlay(OverlayType::TileHover),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 6,
@@ -289,8 +386,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Overlay(OverlayType::TileMarker),
+        self.load_sprite(
+            SpriteKey::Overlay(OverlayType::TileMarker),
             Rect::new(
                 TILE_SIZE * 1,
                 TILE_SIZE * 6,
@@ -298,8 +395,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::Time(DayTime::Morning),
+        self.load_sprite(
+            SpriteKey::Time(DayTime::Morning),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 17,
@@ -307,8 +404,8 @@ impl SpriteTable {
                 TILE_SIZE as u32 * 3,
             ),
         );
-        self.0.insert(
-            TextureType::Time(DayTime::Afternoon),
+        self.load_sprite(
+            SpriteKey::Time(DayTime::Afternoon),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 17,
@@ -316,8 +413,8 @@ impl SpriteTable {
                 TILE_SIZE as u32 * 3,
             ),
         );
-        self.0.insert(
-            TextureType::Time(DayTime::Night),
+        self.load_sprite(
+            SpriteKey::Time(DayTime::Night),
             Rect::new(
                 TILE_SIZE * 10,
                 TILE_SIZE * 17,
@@ -325,8 +422,8 @@ impl SpriteTable {
                 TILE_SIZE as u32 * 3,
             ),
         );
-        self.0.insert(
-            TextureType::EnvCondition(WeatherType::Foggy),
+        self.load_sprite(
+            SpriteKey::EnvCondition(WeatherType::Foggy),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 7,
@@ -334,8 +431,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::EnvCondition(WeatherType::Rainy),
+        self.load_sprite(
+            SpriteKey::EnvCondition(WeatherType::Rainy),
             Rect::new(
                 TILE_SIZE * 0,
                 TILE_SIZE * 8,
@@ -343,8 +440,8 @@ impl SpriteTable {
                 TILE_SIZE as u32 * 3,
             ),
         );
-        self.0.insert(
-            TextureType::EnvCondition(WeatherType::Sunny),
+        self.load_sprite(
+            SpriteKey::EnvCondition(WeatherType::Sunny),
             Rect::new(
                 TILE_SIZE * 3,
                 TILE_SIZE * 7,
@@ -352,8 +449,8 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
-        self.0.insert(
-            TextureType::EnvCondition(WeatherType::TrentinoSnow),
+        self.load_sprite(
+            SpriteKey::EnvCondition(WeatherType::TrentinoSnow),
             Rect::new(
                 TILE_SIZE * 5,
                 TILE_SIZE * 8,
@@ -361,8 +458,8 @@ impl SpriteTable {
                 TILE_SIZE as u32 * 3,
             ),
         );
-        self.0.insert(
-            TextureType::EnvCondition(WeatherType::TropicalMonsoon),
+        self.load_sprite(
+            SpriteKey::EnvCondition(WeatherType::TropicalMonsoon),
             Rect::new(
                 TILE_SIZE * 3,
                 TILE_SIZE * 7,
@@ -370,31 +467,442 @@ impl SpriteTable {
                 TILE_SIZE as u32,
             ),
         );
+
+        // frames as (column, row) of single tiles, the first one is the still sprite
+        let animations = [
+            (
+                SpriteKey::Tile(TileType::ShallowWater),
+                vec![(6, 0), (12, 1), (13, 1)],
+                400,
+                LoopMode::PingPong,
+            ),
+            (
+                SpriteKey::Tile(TileType::DeepWater),
+                vec![(7, 0), (14, 1), (15, 1)],
+                500,
+                LoopMode::PingPong,
+            ),
+            (
+                SpriteKey::Tile(TileType::Teleport(false)),
+                vec![(2, 1), (12, 2)],
+                600,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::Tile(TileType::Teleport(true)),
+                vec![(2, 2), (14, 2), (15, 2)],
+                150,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::Content(ContentKind::Fire),
+                vec![(5, 2), (12, 0), (13, 0)],
+                120,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::RobotWalking(Facing::Up),
+                vec![(8, 0), (8, 1)],
+                150,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::RobotWalking(Facing::Down),
+                vec![(9, 0), (9, 1)],
+                150,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::RobotWalking(Facing::Left),
+                vec![(10, 0), (10, 1)],
+                150,
+                LoopMode::Loop,
+            ),
+            (
+                SpriteKey::RobotWalking(Facing::Right),
+                vec![(11, 0), (11, 1)],
+                150,
+                LoopMode::Loop,
+            ),
+        ];
+        for (key, frames, frame_ms, mode) in animations {
+            let frames = frames
+                .iter()
+                .map(|(col, row)| SpriteRegion {
+                    atlas: DEFAULT_ATLAS_ID,
+                    rect: Rect::new(
+                        TILE_SIZE * col,
+                        TILE_SIZE * row,
+                        TILE_SIZE as u32,
+                        TILE_SIZE as u32,
+                    ),
+                })
+                .collect();
+            let animation = Animation {
+                frames,
+                frame_duration: Duration::from_millis(frame_ms),
+                mode,
+            };
+            self.animations.insert(key, animation);
+        }
     }
     pub fn load_default_font(&mut self) {
-        let mut x = 0;
-        let mut y = TILE_SIZE * 11;
+        self.load_font(&DEFAULT_FONT_LAYOUT, DEFAULT_ATLAS_ID);
+    }
+    // the characters of FONT_STRING are laid out in rows of `columns` tiles starting at `origin`
+    fn load_font(&mut self, layout: &FontLayout, atlas: AtlasId) {
+        let mut x = TILE_SIZE * layout.origin.0 as i32;
+        let mut y = TILE_SIZE * layout.origin.1 as i32;
 
         for c in FONT_STRING.chars() {
-            self.0.insert(
-                TextureType::FontCharater(c, 1.0, true),
+            self.load_atlas_sprite(
+                SpriteKey::FontCharacter(c),
+                atlas,
                 Rect::new(x.clone(), y.clone(), TILE_SIZE as u32, TILE_SIZE as u32),
             );
             x += TILE_SIZE;
-            if x >= TILE_SIZE * 16 {
-                x = 0;
+            if x >= TILE_SIZE * (layout.origin.0 + layout.columns) as i32 {
+                x = TILE_SIZE * layout.origin.0 as i32;
                 y += TILE_SIZE;
             }
         }
     }
 
+    /// Reads an atlas manifest like `assets/atlas.json` and returns the sprites it defines
+    /// together with the paths of its textures, indexed by [`AtlasId`]. Relative paths are
+    /// resolved from the directory of the manifest.
+    ///
+    /// Every tile, content, overlay, daytime and weather sprite has to be defined exactly once,
+    /// all the problems found are reported together.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<(SpriteTable, Vec<PathBuf>), String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let manifest: AtlasManifest =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if manifest.tile_size != TILE_SIZE as u32 {
+            return Err(format!(
+                "{}: tile_size is {}, sprites are drawn with {} pixels per tile",
+                path.display(),
+                manifest.tile_size,
+                TILE_SIZE
+            ));
+        }
+
+        // the names a manifest can use are the ones of the sprites built into the crate
+        let mut defaults = SpriteTable::new();
+        defaults.load_default_sprites();
+        let known = defaults
+            .sprites
+            .keys()
+            .filter_map(|key| manifest_name(key).map(|name| (name, *key)))
+            .collect::<HashMap<_, _>>();
+
+        // `texture` is the default atlas, the named ones follow in alphabetical order
+        let atlas_ids = manifest
+            .atlases
+            .keys()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), DEFAULT_ATLAS_ID + 1 + i))
+            .collect::<HashMap<_, _>>();
+
+        let mut table = SpriteTable::new();
+        let mut errors = Vec::new();
+
+        match sprite_region(&manifest.robot, &atlas_ids) {
+            Ok(region) => {
+                table.sprites.insert(SpriteKey::Robot, region);
+            }
+            Err(e) => errors.push(format!("robot: {}", e)),
+        }
+        let groups = [
+            ("tiles", &manifest.tiles),
+            ("content", &manifest.content),
+            ("overlay", &manifest.overlay),
+            ("daytime", &manifest.daytime),
+            ("weather", &manifest.weather),
+        ];
+        for (group, sprites) in &groups {
+            for (name, values) in sprites.iter() {
+                let Some(key) = known.get(&(*group, name.clone())) else {
+                    errors.push(format!("{}: unknown sprite \"{}\"", group, name));
+                    continue;
+                };
+                match sprite_region(values, &atlas_ids) {
+                    Ok(region) => {
+                        table.sprites.insert(*key, region);
+                    }
+                    Err(e) => errors.push(format!("{}.{}: {}", group, name, e)),
+                }
+            }
+        }
+        for (group, name) in known.keys() {
+            let listed = groups
+                .iter()
+                .any(|(g, sprites)| g == group && sprites.contains_key(name));
+            if !listed {
+                errors.push(format!("{}: missing sprite \"{}\"", group, name));
+            }
+        }
+
+        for (name, levels) in &manifest.content_levels {
+            let Some(SpriteKey::Content(kind)) = known.get(&("content", name.clone())) else {
+                errors.push(format!("content_levels: unknown content \"{}\"", name));
+                continue;
+            };
+            for (quantity, values) in levels {
+                let Ok(quantity) = quantity.parse::<usize>() else {
+                    errors.push(format!(
+                        "content_levels.{}: \"{}\" is not a quantity",
+                        name, quantity
+                    ));
+                    continue;
+                };
+                match sprite_region(values, &atlas_ids) {
+                    Ok(region) => table.load_content_level(*kind, quantity, region),
+                    Err(e) => errors.push(format!("content_levels.{}.{}: {}", name, quantity, e)),
+                }
+            }
+        }
+
+        for (name, values) in &manifest.robot_facing {
+            let Some(facing) = facing_by_name(name) else {
+                errors.push(format!("robot_facing: unknown direction \"{}\"", name));
+                continue;
+            };
+            match sprite_region(values, &atlas_ids) {
+                Ok(region) => {
+                    table.sprites.insert(SpriteKey::RobotFacing(facing), region);
+                }
+                Err(e) => errors.push(format!("robot_facing.{}: {}", name, e)),
+            }
+        }
+
+        let animation_groups = [
+            ("tiles", &manifest.animations.tiles),
+            ("content", &manifest.animations.content),
+            ("robot_walking", &manifest.animations.robot_walking),
+        ];
+        for (group, animations) in animation_groups {
+            for (name, definition) in animations {
+                let key = match group {
+                    "robot_walking" => facing_by_name(name).map(SpriteKey::RobotWalking),
+                    _ => known.get(&(group, name.clone())).copied(),
+                };
+                let Some(key) = key else {
+                    errors.push(format!("animations.{}: unknown sprite \"{}\"", group, name));
+                    continue;
+                };
+                match animation(definition, &atlas_ids) {
+                    Ok(animation) => {
+                        table.animations.insert(key, animation);
+                    }
+                    Err(e) => errors.push(format!("animations.{}.{}: {}", group, name, e)),
+                }
+            }
+        }
+
+        let font = manifest.font.unwrap_or(DEFAULT_FONT_LAYOUT);
+        let font_atlas = match &font.atlas {
+            Some(name) => atlas_ids.get(name.as_str()).copied().unwrap_or_else(|| {
+                errors.push(format!("font: unknown atlas \"{}\"", name));
+                DEFAULT_ATLAS_ID
+            }),
+            None => DEFAULT_ATLAS_ID,
+        };
+
+        if !errors.is_empty() {
+            errors.sort();
+            return Err(format!("{}:\n{}", path.display(), errors.join("\n")));
+        }
+
+        table.load_font(&font, font_atlas);
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let textures = std::iter::once(&manifest.texture)
+            .chain(manifest.atlases.values())
+            .map(|texture| directory.join(texture))
+            .collect();
+        Ok((table, textures))
+    }
+
     //sovrascrive la sprite di un determinato tt
-    pub fn load_sprite(&mut self, tt: TextureType, rect: Rect) {
-        let _ = self.0.insert(tt, rect);
+    pub fn load_sprite(&mut self, key: SpriteKey, rect: Rect) {
+        self.load_atlas_sprite(key, DEFAULT_ATLAS_ID, rect);
+    }
+    pub(crate) fn load_atlas_sprite(&mut self, key: SpriteKey, atlas: AtlasId, rect: Rect) {
+        let _ = self.sprites.insert(key, SpriteRegion { atlas, rect });
+    }
+    // draws content of the given kind with `region` once its quantity reaches `min_quantity`
+    pub(crate) fn load_content_level(
+        &mut self,
+        kind: ContentKind,
+        min_quantity: usize,
+        region: SpriteRegion,
+    ) {
+        let levels = self.content_levels.entry(kind).or_default();
+        levels.retain(|(quantity, _)| *quantity != min_quantity);
+        levels.push((min_quantity, region));
+        levels.sort_by_key(|(quantity, _)| *quantity);
+    }
+}
+
+// layout of the atlas manifest read by SpriteTable::from_file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtlasManifest {
+    texture: PathBuf,
+    // additional textures sprites can be taken from, by name
+    #[serde(default)]
+    atlases: BTreeMap<String, PathBuf>,
+    tile_size: u32,
+    robot: RegionDef,
+    // the robot while it moves, by direction: Up, Down, Left, Right
+    #[serde(default)]
+    robot_facing: HashMap<String, RegionDef>,
+    tiles: HashMap<String, RegionDef>,
+    content: HashMap<String, RegionDef>,
+    overlay: HashMap<String, RegionDef>,
+    daytime: HashMap<String, RegionDef>,
+    weather: HashMap<String, RegionDef>,
+    // content name -> minimum quantity -> region, e.g. `"Tree": { "10": [1, 1] }`
+    #[serde(default)]
+    content_levels: HashMap<String, BTreeMap<String, RegionDef>>,
+    #[serde(default)]
+    animations: AnimationGroups,
+    font: Option<FontLayout>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct AnimationGroups {
+    #[serde(default)]
+    tiles: HashMap<String, AnimationDef>,
+    #[serde(default)]
+    content: HashMap<String, AnimationDef>,
+    // by direction: Up, Down, Left, Right
+    #[serde(default)]
+    robot_walking: HashMap<String, AnimationDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationDef {
+    frames: Vec<RegionDef>,
+    // how long every frame is shown, in milliseconds
+    frame_ms: u64,
+    #[serde(default)]
+    mode: LoopMode,
+}
+
+// `[col, row]` for a single tile or `[col, row, width, height]`, measured in tiles, of the
+// default atlas or `{ "atlas": <name>, "region": [...] }` for one of the named atlases
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RegionDef {
+    Default(Vec<u32>),
+    Atlas { atlas: String, region: Vec<u32> },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontLayout {
+    origin: (u32, u32),
+    columns: u32,
+    atlas: Option<String>,
+}
+
+const DEFAULT_FONT_LAYOUT: FontLayout = FontLayout {
+    origin: (0, 11),
+    columns: 16,
+    atlas: None,
+};
+
+fn sprite_region(
+    definition: &RegionDef,
+    atlas_ids: &HashMap<&str, AtlasId>,
+) -> Result<SpriteRegion, String> {
+    match definition {
+        RegionDef::Default(values) => Ok(SpriteRegion {
+            atlas: DEFAULT_ATLAS_ID,
+            rect: region(values)?,
+        }),
+        RegionDef::Atlas {
+            atlas,
+            region: values,
+        } => Ok(SpriteRegion {
+            atlas: *atlas_ids
+                .get(atlas.as_str())
+                .ok_or_else(|| format!("unknown atlas \"{}\"", atlas))?,
+            rect: region(values)?,
+        }),
+    }
+}
+
+fn region(values: &[u32]) -> Result<Rect, String> {
+    let (col, row, width, height) = match values {
+        [col, row] => (*col, *row, 1, 1),
+        [col, row, width, height] => (*col, *row, *width, *height),
+        _ => return Err("expected [col, row] or [col, row, width, height]".to_string()),
+    };
+    if width == 0 || height == 0 {
+        return Err("the region is empty".to_string());
+    }
+    Ok(Rect::new(
+        TILE_SIZE * col as i32,
+        TILE_SIZE * row as i32,
+        TILE_SIZE as u32 * width,
+        TILE_SIZE as u32 * height,
+    ))
+}
+
+fn animation(
+    definition: &AnimationDef,
+    atlas_ids: &HashMap<&str, AtlasId>,
+) -> Result<Animation, String> {
+    if definition.frames.is_empty() {
+        return Err("an animation needs at least one frame".to_string());
+    }
+    if definition.frame_ms == 0 {
+        return Err("frame_ms has to be greater than 0".to_string());
+    }
+    let frames = definition
+        .frames
+        .iter()
+        .map(|frame| sprite_region(frame, atlas_ids))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Animation {
+        frames,
+        frame_duration: Duration::from_millis(definition.frame_ms),
+        mode: definition.mode,
+    })
+}
+
+fn facing_by_name(name: &str) -> Option<Facing> {
+    match name {
+        "Up" => Some(Facing::Up),
+        "Down" => Some(Facing::Down),
+        "Left" => Some(Facing::Left),
+        "Right" => Some(Facing::Right),
+        _ => None,
+    }
+}
+
+// the group and name a sprite is listed under in an atlas manifest
+fn manifest_name(key: &SpriteKey) -> Option<(&'static str, String)> {
+    match key {
+        SpriteKey::Tile(tt) => Some(("tiles", format!("{:?}", tt))),
+        SpriteKey::Content(kind) => Some(("content", format!("{:?}", kind))),
+        SpriteKey::Overlay(ot) => Some(("overlay", format!("{:?}", ot))),
+        SpriteKey::Time(dt) => Some(("daytime", format!("{:?}", dt))),
+        SpriteKey::EnvCondition(wt) => Some(("weather", format!("{:?}", wt))),
+        _ => None,
     }
 }
 
-#[derive(Debug, Clone)]
+/// What an entity draws: the sprite plus how to place it on the screen.
+#[derive(Debug, Clone, PartialEq)]
 pub enum TextureType {
     Robot,
     Tile(TileType),
@@ -405,86 +913,316 @@ pub enum TextureType {
     FontCharater(char, f32, bool),
     Item(Box<TextureType>, f32, bool),
     Square((u32, u32), Color, bool, bool), // centered, fixed
+    Line(Point, Color),                    // the other end, in world coordinates
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum OverlayType {
     TileHover,
     TileMarker,
 }
 
-impl PartialEq for TextureType {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Tile(l0), Self::Tile(r0)) => l0 == r0,
-            (Self::Content(l0), Self::Content(r0)) => l0 == r0,
-            (Self::Overlay(l0), Self::Overlay(r0)) => l0 == r0,
-            (Self::Time(l0), Self::Time(r0)) => l0 == r0,
-            (Self::EnvCondition(l0), Self::EnvCondition(r0)) => l0 == r0,
-            (Self::FontCharater(l0, _, _), Self::FontCharater(r0, _, _)) => l0 == r0,
-            (Self::Item(l0, _, _), Self::Item(r0, _, _)) => *l0.clone() == *r0.clone(),
-            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+/// Key of a sprite in the sprite table, content is identified by its kind only, the quantity
+/// picks one of its levels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpriteKey {
+    Robot,
+    Tile(TileType),
+    Content(ContentKind),
+    Overlay(OverlayType),
+    Time(DayTime),
+    EnvCondition(WeatherType),
+    FontCharacter(char),
+    // the robot looking towards a direction while it moves, Robot is used when it stands still
+    RobotFacing(Facing),
+    // the frames of the robot moving in a direction, only used for animations
+    RobotWalking(Facing),
+}
+
+/// The direction a sprite is looking at, see [`Direction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Facing {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+pub(crate) const FACINGS: [Facing; 4] = [Facing::Up, Facing::Down, Facing::Left, Facing::Right];
+
+impl From<&Direction> for Facing {
+    fn from(direction: &Direction) -> Self {
+        match direction {
+            Direction::Up => Facing::Up,
+            Direction::Down => Facing::Down,
+            Direction::Left => Facing::Left,
+            Direction::Right => Facing::Right,
         }
     }
 }
-impl Eq for TextureType {}
-impl Hash for TextureType {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+
+impl Eq for SpriteKey {}
+// the robotics_lib types are hashed through their position in the enum, they do not implement
+// Hash themselves
+impl Hash for SpriteKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
         core::mem::discriminant(self).hash(state);
+        match self {
+            SpriteKey::Robot => {}
+            SpriteKey::Tile(tt) => tile_index(*tt).hash(state),
+            SpriteKey::Content(kind) => kind.hash(state),
+            SpriteKey::Overlay(ot) => ot.hash(state),
+            SpriteKey::Time(dt) => (*dt as u8).hash(state),
+            SpriteKey::EnvCondition(wt) => (*wt as u8).hash(state),
+            SpriteKey::FontCharacter(c) => c.hash(state),
+            SpriteKey::RobotFacing(facing) | SpriteKey::RobotWalking(facing) => facing.hash(state),
+        }
     }
 }
 
-pub(crate) fn get_texture_type_from_content(content: Content) -> TextureType {
-    // map content to texture type
+fn tile_index(tile_type: TileType) -> u8 {
+    match tile_type {
+        TileType::DeepWater => 0,
+        TileType::ShallowWater => 1,
+        TileType::Sand => 2,
+        TileType::Grass => 3,
+        TileType::Street => 4,
+        TileType::Hill => 5,
+        TileType::Mountain => 6,
+        TileType::Snow => 7,
+        TileType::Lava => 8,
+        TileType::Teleport(false) => 9,
+        TileType::Teleport(true) => 10,
+        TileType::Wall => 11,
+    }
+}
+
+/// A [`Content`] without its quantity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    Rock,
+    Tree,
+    Garbage,
+    Fire,
+    Coin,
+    Bin,
+    Crate,
+    Bank,
+    Water,
+    Market,
+    Fish,
+    Building,
+    Bush,
+    JollyBlock,
+    Scarecrow,
+    None,
+}
+
+impl From<&Content> for ContentKind {
+    fn from(content: &Content) -> Self {
+        match content {
+            Content::Rock(_) => ContentKind::Rock,
+            Content::Tree(_) => ContentKind::Tree,
+            Content::Garbage(_) => ContentKind::Garbage,
+            Content::Fire => ContentKind::Fire,
+            Content::Coin(_) => ContentKind::Coin,
+            Content::Bin(_) => ContentKind::Bin,
+            Content::Crate(_) => ContentKind::Crate,
+            Content::Bank(_) => ContentKind::Bank,
+            Content::Water(_) => ContentKind::Water,
+            Content::Market(_) => ContentKind::Market,
+            Content::Fish(_) => ContentKind::Fish,
+            Content::Building => ContentKind::Building,
+            Content::Bush(_) => ContentKind::Bush,
+            Content::JollyBlock(_) => ContentKind::JollyBlock,
+            Content::Scarecrow => ContentKind::Scarecrow,
+            Content::None => ContentKind::None,
+        }
+    }
+}
+
+// the amount that selects the sprite level, containers use how full they are
+fn content_quantity(content: &Content) -> usize {
     match content {
-        Content::Bin(_) => TextureType::Content(Content::Bin(0..0)),
-        Content::Rock(_) => TextureType::Content(Content::Rock(0)),
-        Content::Tree(_) => TextureType::Content(Content::Tree(0)),
-        Content::Garbage(_) => TextureType::Content(Content::Garbage(0)),
-        Content::Fire => TextureType::Content(Content::Fire),
-        Content::Coin(_) => TextureType::Content(Content::Coin(0)),
-        Content::Crate(_) => TextureType::Content(Content::Crate(0..0)),
-        Content::Bank(_) => TextureType::Content(Content::Bank(0..0)),
-        Content::Water(_) => TextureType::Content(Content::Water(0)),
-        Content::Market(_) => TextureType::Content(Content::Market(0)),
-        Content::Fish(_) => TextureType::Content(Content::Fish(0)),
-        Content::Building => TextureType::Content(Content::Building),
-        Content::Bush(_) => TextureType::Content(Content::Bush(0)),
-        Content::JollyBlock(_) => TextureType::Content(Content::JollyBlock(0)),
-        Content::Scarecrow => TextureType::Content(Content::Scarecrow),
-        Content::None => TextureType::Content(Content::None),
+        Content::Rock(n)
+        | Content::Tree(n)
+        | Content::Garbage(n)
+        | Content::Coin(n)
+        | Content::Water(n)
+        | Content::Market(n)
+        | Content::Fish(n)
+        | Content::Bush(n)
+        | Content::JollyBlock(n) => *n,
+        Content::Bin(range) | Content::Crate(range) | Content::Bank(range) => range.start,
+        Content::Fire | Content::Building | Content::Scarecrow | Content::None => 0,
     }
 }
+
 // implements tostring for TextureType
 impl TextureType {
+    /// Returns the key of the sprite drawn for this texture type, squares and lines have none.
+    pub fn sprite_key(&self) -> Option<SpriteKey> {
+        match self {
+            TextureType::Robot => Some(SpriteKey::Robot),
+            TextureType::Tile(tt) => Some(SpriteKey::Tile(*tt)),
+            TextureType::Content(c) => Some(SpriteKey::Content(ContentKind::from(c))),
+            TextureType::Overlay(ot) => Some(SpriteKey::Overlay(*ot)),
+            TextureType::Time(dt) => Some(SpriteKey::Time(*dt)),
+            TextureType::EnvCondition(wt) => Some(SpriteKey::EnvCondition(*wt)),
+            TextureType::FontCharater(c, _, _) => Some(SpriteKey::FontCharacter(*c)),
+            TextureType::Item(tt, _, _) => tt.sprite_key(),
+            TextureType::Square(..) | TextureType::Line(..) => None,
+        }
+    }
     pub fn to_string(&self) -> String {
         match self {
             TextureType::Robot => "Robot".to_string(),
             TextureType::Tile(tt) => format!("{:?}", tt),
-            TextureType::Content(c) => match *c {
-                Content::Bin(_) => "Bin".to_string(),
-                Content::Rock(_) => "Rock".to_string(),
-                Content::Tree(_) => "Tree".to_string(),
-                Content::Garbage(_) => "Garbage".to_string(),
-                Content::Fire => "Fire".to_string(),
-                Content::Coin(_) => "Coin".to_string(),
-                Content::Crate(_) => "Crate".to_string(),
-                Content::Bank(_) => "Bank".to_string(),
-                Content::Water(_) => "Water".to_string(),
-                Content::Market(_) => "Market".to_string(),
-                Content::Fish(_) => "Fish".to_string(),
-                Content::Building => "Building".to_string(),
-                Content::Bush(_) => "Bush".to_string(),
-                Content::JollyBlock(_) => "JollyBlock".to_string(),
-                Content::Scarecrow => "Scarecrow".to_string(),
-                Content::None => "None".to_string(),
-            },
+            TextureType::Content(c) => format!("{:?}", ContentKind::from(c)),
             TextureType::Overlay(ot) => format!("{:?}", ot),
             TextureType::Time(dt) => format!("{:?}", dt),
             TextureType::EnvCondition(wt) => format!("{:?}", wt),
             TextureType::FontCharater(c, _, _) => format!("{:?}", c),
             TextureType::Item(tt, _, _) => format!("{:?}", tt),
             TextureType::Square((_, _), _, _, _) => "Square".to_string(),
+            TextureType::Line(_, _) => "Line".to_string(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn hash_of(key: &SpriteKey) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn keys() -> Vec<SpriteKey> {
+        let mut keys = vec![
+            SpriteKey::Robot,
+            SpriteKey::Tile(TileType::Grass),
+            SpriteKey::Tile(TileType::Teleport(false)),
+            SpriteKey::Tile(TileType::Teleport(true)),
+            SpriteKey::Content(ContentKind::Tree),
+            SpriteKey::Content(ContentKind::None),
+            SpriteKey::Overlay(OverlayType::TileHover),
+            SpriteKey::Overlay(OverlayType::TileMarker),
+            SpriteKey::Time(DayTime::Morning),
+            SpriteKey::Time(DayTime::Night),
+            SpriteKey::EnvCondition(WeatherType::Sunny),
+            SpriteKey::EnvCondition(WeatherType::Rainy),
+            SpriteKey::FontCharacter('a'),
+            SpriteKey::FontCharacter('b'),
+        ];
+        for facing in FACINGS {
+            keys.push(SpriteKey::RobotFacing(facing));
+            keys.push(SpriteKey::RobotWalking(facing));
+        }
+        keys
+    }
+
+    #[test]
+    fn equal_keys_hash_equally() {
+        for (a, b) in keys().iter().zip(keys().iter()) {
+            assert_eq!(a, b);
+            assert_eq!(hash_of(a), hash_of(b), "{:?}", a);
+        }
+        // content with different quantities shares the key of its kind
+        let tree = |n| TextureType::Content(Content::Tree(n)).sprite_key().unwrap();
+        assert_eq!(tree(1), tree(7));
+        assert_eq!(hash_of(&tree(1)), hash_of(&tree(7)));
+    }
+
+    #[test]
+    fn different_keys_are_different_entries() {
+        let keys = keys();
+        let set = keys.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(set.len(), keys.len());
+        for key in &keys {
+            assert!(set.contains(key), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn content_uses_the_highest_level_its_quantity_reaches() {
+        let region = |col| SpriteRegion {
+            atlas: DEFAULT_ATLAS_ID,
+            rect: Rect::new(col * TILE_SIZE, 0, TILE_SIZE as u32, TILE_SIZE as u32),
+        };
+        let mut table = SpriteTable::new();
+        table.load_sprite(SpriteKey::Content(ContentKind::Tree), region(0).rect);
+        table.load_content_level(ContentKind::Tree, 10, region(2));
+        table.load_content_level(ContentKind::Tree, 5, region(1));
+        table.load_sprite(SpriteKey::Content(ContentKind::Bin), region(3).rect);
+        table.load_content_level(ContentKind::Bin, 4, region(4));
+
+        let tree = |n| table.region(&TextureType::Content(Content::Tree(n)));
+        assert_eq!(tree(0), Some(region(0)));
+        assert_eq!(tree(4), Some(region(0)));
+        assert_eq!(tree(5), Some(region(1)));
+        assert_eq!(tree(9), Some(region(1)));
+        assert_eq!(tree(10), Some(region(2)));
+        assert_eq!(tree(100), Some(region(2)));
+
+        // containers are picked by how full they are, the start of their range
+        let bin = |range| table.region(&TextureType::Content(Content::Bin(range)));
+        assert_eq!(bin(3..10), Some(region(3)));
+        assert_eq!(bin(4..5), Some(region(4)));
+
+        // loading a level again replaces it
+        table.load_content_level(ContentKind::Tree, 5, region(3));
+        assert_eq!(
+            table.region(&TextureType::Content(Content::Tree(6))),
+            Some(region(3))
+        );
+    }
+
+    #[test]
+    fn bundled_manifest_matches_the_default_table() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/atlas.json");
+        let (bundled, _) = SpriteTable::from_file(path).unwrap();
+        let mut defaults = SpriteTable::new();
+        defaults.load_default_sprites();
+
+        assert_eq!(bundled.animations.len(), defaults.animations.len());
+        for (key, animation) in &defaults.animations {
+            assert_eq!(bundled.animation(key), Some(animation), "{:?}", key);
+        }
+        for facing in FACINGS {
+            let key = SpriteKey::RobotFacing(facing);
+            assert!(defaults.sprite(&key).is_some(), "{:?}", key);
+            assert_eq!(bundled.sprite(&key), defaults.sprite(&key), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn only_tiles_whose_frame_changed_are_returned() {
+        let mut table = SpriteTable::new();
+        table.load_default_sprites();
+        let changed = |before: u64, now: u64| {
+            let changed = table
+                .changed_tile_frames(Duration::from_millis(before), Duration::from_millis(now));
+            changed.into_iter().collect::<HashSet<_>>()
+        };
+
+        // the fire changes frame as well, but it is content
+        assert_eq!(
+            changed(0, 150),
+            HashSet::from([SpriteKey::Tile(TileType::Teleport(true))])
+        );
+        assert_eq!(
+            changed(150, 400),
+            HashSet::from([
+                SpriteKey::Tile(TileType::Teleport(true)),
+                SpriteKey::Tile(TileType::ShallowWater),
+            ])
+        );
+        assert!(changed(0, 100).is_empty());
+    }
+}
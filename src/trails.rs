@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use crate::RobotId;
+
+// only the most recent part of a trail is drawn
+pub(crate) const DRAWN_TRAIL_LENGTH: usize = 64;
+// alpha of the oldest and of the newest drawn position
+const TRAIL_ALPHA: (u8, u8) = (24, 200);
+
+// the (row, col) of every tile given to update_robot, per robot, oldest first, ordered by robot so
+// that the trails are always drawn in the same order
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Trails(BTreeMap<RobotId, Vec<(usize, usize)>>);
+
+impl Trails {
+    pub(crate) fn new() -> Self {
+        Trails(BTreeMap::new())
+    }
+
+    // returns false if the robot was already on that tile
+    pub(crate) fn push(&mut self, id: RobotId, coords: (usize, usize)) -> bool {
+        let trail = self.0.entry(id).or_default();
+        if trail.last() == Some(&coords) {
+            return false;
+        }
+        trail.push(coords);
+        true
+    }
+
+    pub(crate) fn get(&self, id: RobotId) -> Vec<(usize, usize)> {
+        self.0.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn clear(&mut self, id: RobotId) {
+        self.0.remove(&id);
+    }
+
+    // the drawn part of every trail with the alpha of each position, fading out towards the
+    // oldest one
+    pub(crate) fn fading(&self) -> Vec<(RobotId, (usize, usize), u8)> {
+        let mut dots = Vec::new();
+        for (id, trail) in &self.0 {
+            let drawn = &trail[trail.len().saturating_sub(DRAWN_TRAIL_LENGTH)..];
+            for (i, coords) in drawn.iter().enumerate() {
+                let (oldest, newest) = (TRAIL_ALPHA.0 as usize, TRAIL_ALPHA.1 as usize);
+                let alpha = match drawn.len() {
+                    1 => newest,
+                    len => oldest + (newest - oldest) * i / (len - 1),
+                };
+                dots.push((*id, *coords, alpha as u8));
+            }
+        }
+        dots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_skips_the_tile_the_robot_is_on() {
+        let mut trails = Trails::new();
+        assert!(trails.push(RobotId(0), (1, 1)));
+        assert!(!trails.push(RobotId(0), (1, 1)));
+        assert!(trails.push(RobotId(0), (1, 2)));
+        // coming back to an older tile is a new position
+        assert!(trails.push(RobotId(0), (1, 1)));
+        // every robot has its own trail
+        assert!(trails.push(RobotId(1), (1, 1)));
+
+        assert_eq!(trails.get(RobotId(0)), [(1, 1), (1, 2), (1, 1)]);
+        assert_eq!(trails.get(RobotId(1)), [(1, 1)]);
+
+        trails.clear(RobotId(0));
+        assert!(trails.get(RobotId(0)).is_empty());
+        assert!(trails.push(RobotId(0), (1, 1)));
+    }
+
+    #[test]
+    fn only_the_most_recent_positions_are_drawn() {
+        let mut trails = Trails::new();
+        for col in 0..DRAWN_TRAIL_LENGTH + 10 {
+            trails.push(RobotId(0), (0, col));
+        }
+        assert_eq!(trails.get(RobotId(0)).len(), DRAWN_TRAIL_LENGTH + 10);
+
+        let dots = trails.fading();
+        assert_eq!(dots.len(), DRAWN_TRAIL_LENGTH);
+        assert_eq!(dots.first().unwrap().1, (0, 10));
+        assert_eq!(dots.last().unwrap().1, (0, DRAWN_TRAIL_LENGTH + 9));
+    }
+
+    #[test]
+    fn trails_fade_out_towards_the_oldest_position() {
+        let mut trails = Trails::new();
+        for col in 0..5 {
+            trails.push(RobotId(1), (0, col));
+        }
+        trails.push(RobotId(0), (3, 3));
+
+        let dots = trails.fading();
+        // robots come in the order of their ids
+        assert_eq!(dots[0], (RobotId(0), (3, 3), TRAIL_ALPHA.1));
+
+        let alphas = dots[1..].iter().map(|dot| dot.2).collect::<Vec<_>>();
+        assert_eq!(alphas.first(), Some(&TRAIL_ALPHA.0));
+        assert_eq!(alphas.last(), Some(&TRAIL_ALPHA.1));
+        assert!(
+            alphas.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            alphas
+        );
+    }
+}
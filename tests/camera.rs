@@ -0,0 +1,168 @@
+use robo_gui::camera::{Camera, MIN_SCALE};
+use robo_gui::TILE_SIZE;
+
+const VIEWPORT: (u32, u32) = (1280, 720);
+
+// every zoom level reachable with the mouse wheel, from one pixel per tile to 4x
+fn zoom_levels() -> impl Iterator<Item = f32> {
+    (1..=TILE_SIZE * 4).map(|step| step as f32 * MIN_SCALE)
+}
+
+fn cameras() -> impl Iterator<Item = Camera> {
+    zoom_levels().flat_map(|scale| {
+        [(0.0, 0.0), (37.0, -12.5), (-1000.0, 640.0)]
+            .into_iter()
+            .map(move |pan| {
+                let mut camera = Camera::new(VIEWPORT);
+                camera.set_scale(scale);
+                camera.set_pan(pan);
+                camera
+            })
+    })
+}
+
+fn screen_points() -> impl Iterator<Item = (f32, f32)> {
+    (0..=VIEWPORT.0).step_by(7).flat_map(|x| {
+        (0..=VIEWPORT.1)
+            .step_by(11)
+            .map(move |y| (x as f32, y as f32))
+    })
+}
+
+#[test]
+fn screen_world_screen_round_trip() {
+    for camera in cameras() {
+        for point in screen_points() {
+            let back = camera.world_to_screen(camera.screen_to_world(point));
+            assert!(
+                (back.0 - point.0).abs() < 1e-2 && (back.1 - point.1).abs() < 1e-2,
+                "{:?} came back as {:?} at {:?}",
+                point,
+                back,
+                camera
+            );
+        }
+    }
+}
+
+#[test]
+fn screen_map_screen_round_trip() {
+    for camera in cameras() {
+        let half_tile = TILE_SIZE as f32 * camera.scale() / 2.0;
+        for point in screen_points() {
+            let tile = camera.screen_to_map(point);
+            let center = camera.map_to_screen(tile);
+
+            // the point has to lie on the tile it was mapped to...
+            assert!(
+                (center.0 - point.0).abs() <= half_tile + 1e-2
+                    && (center.1 - point.1).abs() <= half_tile + 1e-2,
+                "{:?} mapped to {:?} centered on {:?} at {:?}",
+                point,
+                tile,
+                center,
+                camera
+            );
+            // ...and the centre of that tile has to map back to the same tile
+            assert_eq!(camera.screen_to_map(center), tile, "at {:?}", camera);
+        }
+    }
+}
+
+#[test]
+fn zoom_keeps_the_pivot_in_place() {
+    let mut camera = Camera::new(VIEWPORT);
+    camera.set_pan((123.0, -45.0));
+    let pivot = camera.pivot();
+    let world = camera.screen_to_world(pivot);
+
+    for scale in zoom_levels() {
+        camera.set_scale(scale);
+        let screen = camera.world_to_screen(world);
+        assert!((screen.0 - pivot.0).abs() < 1e-3 && (screen.1 - pivot.1).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn scale_is_clamped() {
+    let mut camera = Camera::new(VIEWPORT);
+    camera.set_scale(0.0);
+    assert_eq!(camera.scale(), MIN_SCALE);
+}
+
+#[test]
+fn zoom_towards_keeps_the_cursor_in_place() {
+    let mut camera = Camera::new(VIEWPORT);
+    camera.set_pan((-300.0, 80.0));
+    let cursor = (1000.0, 150.0);
+    let world = camera.screen_to_world(cursor);
+
+    camera.zoom_towards(cursor, 3.0);
+    let mut frames = 0;
+    while camera.scale() != camera.target_scale() {
+        camera.update_zoom();
+        let screen = camera.world_to_screen(world);
+        assert!((screen.0 - cursor.0).abs() < 1e-2 && (screen.1 - cursor.1).abs() < 1e-2);
+
+        frames += 1;
+        assert!(frames < 100, "zoom never reached its target");
+    }
+    assert_eq!(camera.scale(), 3.0);
+}
+
+#[test]
+fn zoom_respects_bounds() {
+    let mut camera = Camera::new(VIEWPORT);
+    camera.set_scale_bounds(0.5, 2.0);
+
+    camera.zoom_towards((0.0, 0.0), 100.0);
+    assert_eq!(camera.target_scale(), 2.0);
+    camera.zoom_towards((0.0, 0.0), 0.001);
+    assert_eq!(camera.target_scale(), 0.5);
+}
+
+#[test]
+fn follow_ignores_targets_inside_the_dead_zone() {
+    let mut camera = Camera::new(VIEWPORT);
+    camera.set_follow((200, 100), 0.5);
+    let pan = camera.pan();
+
+    let target = camera.screen_to_world((camera.pivot().0 + 90.0, camera.pivot().1 - 40.0));
+    camera.follow(target);
+    assert_eq!(camera.pan(), pan);
+}
+
+#[test]
+fn follow_brings_the_target_back_into_the_dead_zone() {
+    for scale in zoom_levels() {
+        let mut camera = Camera::new(VIEWPORT);
+        camera.set_scale(scale);
+        camera.set_follow((200, 100), 0.3);
+        let target = camera.screen_to_world((5.0, 700.0));
+
+        for _ in 0..200 {
+            camera.follow(target);
+        }
+        let (x, y) = camera.world_to_screen(target);
+        let pivot = camera.pivot();
+        assert!((x - pivot.0).abs() <= 100.5 && (y - pivot.1).abs() <= 50.5);
+    }
+}
+
+#[test]
+fn visible_tiles_cover_the_viewport() {
+    for camera in cameras() {
+        let ((min_col, min_row), (max_col, max_row)) = camera.visible_tiles();
+        let half_tile = TILE_SIZE as f32 * camera.scale() / 2.0;
+
+        // the tiles right outside of the range have to be completely off screen
+        let (left, _) = camera.map_to_screen((min_col - 1, min_row));
+        let (right, _) = camera.map_to_screen((max_col + 1, max_row));
+        let (_, top) = camera.map_to_screen((min_col, min_row - 1));
+        let (_, bottom) = camera.map_to_screen((max_col, max_row + 1));
+        assert!(left + half_tile <= 1e-2);
+        assert!(right - half_tile >= VIEWPORT.0 as f32 - 1e-2);
+        assert!(top + half_tile <= 1e-2);
+        assert!(bottom - half_tile >= VIEWPORT.1 as f32 - 1e-2);
+    }
+}
@@ -0,0 +1,19 @@
+// helpers shared by the integration tests
+//
+// sdl can only be used from one thread, so every test file runs everything that needs a
+// MainState inside a single test
+#![allow(dead_code)]
+
+use robotics_lib::world::tile::{Content, Tile, TileType};
+
+pub fn tile(tile_type: TileType, content: Content) -> Option<Tile> {
+    Some(Tile {
+        tile_type,
+        content,
+        elevation: 0,
+    })
+}
+
+pub fn grass_field(size: usize) -> Vec<Vec<Option<Tile>>> {
+    vec![vec![tile(TileType::Grass, Content::None); size]; size]
+}
@@ -0,0 +1,375 @@
+// Golden-image regression tests for the renderer.
+//
+// Every case builds a small fixed map, drives a headless MainState through
+// update_world/update_robot/tick and compares the rendered frame with
+// `tests/golden/<reference>.png`.
+// Set ROBO_GUI_BLESS=1 to write the references, after an intended visual change or for a new
+// case, and commit them. A missing reference fails the case.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+mod common;
+
+use common::{grass_field, tile};
+use robo_gui::MainState;
+use robotics_lib::world::environmental_conditions::{DayTime, WeatherType};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::surface::Surface;
+
+struct Tolerance {
+    // max difference allowed on a single channel before a pixel counts as different
+    channel: u8,
+    // fraction of pixels that are allowed to differ
+    mismatched: f32,
+}
+
+const EXACT: Tolerance = Tolerance {
+    channel: 0,
+    mismatched: 0.0,
+};
+const LOOSE: Tolerance = Tolerance {
+    channel: 8,
+    mismatched: 0.001,
+};
+
+struct GoldenCase {
+    name: &'static str,
+    // cases that have to look exactly like another one share its reference image, which is
+    // named after that case
+    reference: &'static str,
+    tolerance: Tolerance,
+    run: fn(&mut MainState) -> Result<(), String>,
+}
+
+fn mixed_world() -> Vec<Vec<Option<Tile>>> {
+    vec![
+        vec![
+            tile(TileType::DeepWater, Content::None),
+            tile(TileType::ShallowWater, Content::Fish(2)),
+            tile(TileType::Sand, Content::None),
+            tile(TileType::Grass, Content::Tree(3)),
+            tile(TileType::Hill, Content::Rock(1)),
+        ],
+        vec![
+            tile(TileType::ShallowWater, Content::None),
+            tile(TileType::Sand, Content::Coin(5)),
+            tile(TileType::Grass, Content::None),
+            tile(TileType::Street, Content::Building),
+            tile(TileType::Mountain, Content::None),
+        ],
+        vec![
+            tile(TileType::Sand, Content::Garbage(1)),
+            tile(TileType::Grass, Content::Bush(1)),
+            tile(TileType::Street, Content::None),
+            tile(TileType::Street, Content::Bank(0..10)),
+            tile(TileType::Snow, Content::None),
+        ],
+        vec![
+            None,
+            tile(TileType::Grass, Content::Fire),
+            tile(TileType::Street, Content::Market(3)),
+            tile(TileType::Teleport(false), Content::None),
+            tile(TileType::Lava, Content::None),
+        ],
+        vec![
+            None,
+            None,
+            tile(TileType::Wall, Content::None),
+            tile(TileType::Grass, Content::Scarecrow),
+            tile(TileType::Grass, Content::Bin(0..5)),
+        ],
+    ]
+}
+
+// two paths with an arrow and one that stays on its tile and is drawn as a dot
+fn set_paths(state: &mut MainState) {
+    state.set_planned_path(
+        "route",
+        vec![(2, 2), (2, 3), (1, 3), (1, 4)],
+        Color::RGB(0, 120, 255),
+    );
+    state.set_planned_path("back", vec![(3, 1), (3, 0)], Color::RGB(255, 120, 0));
+    state.set_planned_path("wait", vec![(0, 0), (0, 0)], Color::RGB(255, 255, 255));
+}
+
+fn cases() -> Vec<GoldenCase> {
+    vec![
+        GoldenCase {
+            name: "grass_field",
+            reference: "grass_field",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "mixed_world",
+            reference: "mixed_world",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(mixed_world());
+                state.update_robot(robot, Some((2, 2)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "mixed_world_after_grass",
+            reference: "mixed_world",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(7));
+                state.update_world(mixed_world());
+                state.update_robot(robot, Some((2, 2)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "mixed_world_from_tiles",
+            reference: "mixed_world",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                let changes = mixed_world()
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(row, tiles)| {
+                        tiles
+                            .into_iter()
+                            .enumerate()
+                            .map(move |(col, tile)| (row, col, tile))
+                    })
+                    .collect::<Vec<_>>();
+                state.update_tiles(&changes);
+                state.update_robot(robot, Some((2, 2)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "mixed_world_from_manifest",
+            reference: "mixed_world",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(mixed_world());
+                // the manifest shipped with the crate describes the embedded atlas
+                state.load_sprite_atlas(
+                    Path::new(env!("CARGO_MANIFEST_DIR"))
+                        .join("assets")
+                        .join("atlas.json"),
+                )?;
+                state.update_robot(robot, Some((2, 2)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "robot_moved_right",
+            reference: "robot_moved_right",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(mixed_world());
+                state.update_robot(robot, Some((2, 3)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "two_robots",
+            reference: "two_robots",
+            tolerance: EXACT,
+            run: |state| {
+                let first = state.add_robot(1, 1);
+                let second = state.add_robot(3, 3);
+                state.update_world(grass_field(5));
+                state.set_robot_tint(first, Some(Color::RGB(220, 60, 60)))?;
+                state.set_robot_label(first, Some("red"))?;
+                state.set_robot_tint(second, Some(Color::RGB(60, 60, 220)))?;
+                state.set_robot_label(second, Some("blue"))?;
+                // each robot heads for its own tile
+                state.update_robot(first, Some((1, 2)))?;
+                state.update_robot(second, Some((2, 3)))?;
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "planned_paths",
+            reference: "planned_paths",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                set_paths(state);
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "planned_path_replaced",
+            reference: "planned_paths",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                state.set_planned_path("route", vec![(4, 4), (4, 0)], Color::RGB(0, 0, 0));
+                set_paths(state);
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "planned_path_cleared",
+            reference: "planned_paths",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                set_paths(state);
+                state.set_planned_path("extra", vec![(4, 4), (4, 0)], Color::RGB(0, 0, 0));
+                state.clear_planned_path("extra");
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "planned_paths_cleared",
+            reference: "grass_field",
+            tolerance: EXACT,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                set_paths(state);
+                state.clear_planned_paths();
+                state.tick()
+            },
+        },
+        GoldenCase {
+            name: "environment",
+            reference: "environment",
+            tolerance: LOOSE,
+            run: |state| {
+                let robot = state.add_robot(2, 2);
+                state.update_world(grass_field(5));
+                state.update_robot(robot, Some((2, 2)))?;
+                state.update_time_of_day(DayTime::Night);
+                state.update_weather(WeatherType::Rainy);
+                state.update_energy(500);
+                state.tick()
+            },
+        },
+    ]
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+fn save_png(width: u32, height: u32, mut pixels: Vec<u8>, path: &Path) -> Result<(), String> {
+    Surface::from_data(
+        &mut pixels,
+        width,
+        height,
+        width * 4,
+        PixelFormatEnum::RGBA32,
+    )?
+    .save(path)
+}
+
+fn load_png(path: &Path) -> Result<(u32, u32, Vec<u8>), String> {
+    let surface = Surface::from_file(path)?.convert_format(PixelFormatEnum::RGBA32)?;
+    let (width, height, pitch) = (surface.width(), surface.height(), surface.pitch());
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    surface.with_lock(|data| {
+        for row in 0..height {
+            let start = (row * pitch) as usize;
+            pixels.extend_from_slice(&data[start..start + (width * 4) as usize]);
+        }
+    });
+    Ok((width, height, pixels))
+}
+
+// returns how many pixels differ by more than the channel tolerance
+fn count_mismatches(expected: &[u8], actual: &[u8], tolerance: &Tolerance) -> usize {
+    expected
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .filter(|(e, a)| {
+            e.iter()
+                .zip(a.iter())
+                .any(|(e, a)| e.abs_diff(*a) > tolerance.channel)
+        })
+        .count()
+}
+
+fn check(case: &GoldenCase) -> Result<(), String> {
+    let mut state = MainState::new_headless(3)?;
+    (case.run)(&mut state)?;
+    let (width, height, actual) = state.read_frame()?;
+
+    let reference = golden_dir().join(format!("{}.png", case.reference));
+    // only the case a reference is named after writes it, the others are still compared with it
+    if env::var_os("ROBO_GUI_BLESS").is_some() && case.name == case.reference {
+        return save_png(width, height, actual, &reference);
+    }
+    if !reference.exists() {
+        return Err(format!(
+            "{}: missing reference {}, run with ROBO_GUI_BLESS=1 to write it",
+            case.name,
+            reference.display()
+        ));
+    }
+
+    let (ref_width, ref_height, expected) = load_png(&reference)?;
+    if (ref_width, ref_height) != (width, height) {
+        return Err(format!(
+            "{}: size {}x{} does not match the reference {}x{}",
+            case.name, width, height, ref_width, ref_height
+        ));
+    }
+
+    let mismatched = count_mismatches(&expected, &actual, &case.tolerance);
+    let allowed = (case.tolerance.mismatched * (width * height) as f32) as usize;
+    if mismatched > allowed {
+        let actual_path = golden_dir().join(format!("{}.actual.png", case.name));
+        save_png(width, height, actual, &actual_path)?;
+        return Err(format!(
+            "{}: {} pixels differ from the reference (allowed {}), output written to {}",
+            case.name,
+            mismatched,
+            allowed,
+            actual_path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn golden_images() {
+    let cases = cases();
+    for case in &cases {
+        assert!(
+            cases.iter().any(|other| other.name == case.reference),
+            "{}: no case is named after the reference {}",
+            case.name,
+            case.reference
+        );
+    }
+
+    let failures = cases
+        .iter()
+        .filter_map(|case| check(case).err())
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+
+mod common;
+
+use common::grass_field;
+use robo_gui::MainState;
+use sdl2::pixels::Color;
+
+#[test]
+fn markers_menu_shows_any_marker() {
+    // a session is the only way to open the markers menu without a keyboard
+    let path = env::temp_dir().join(format!("robo_gui_markers_{}.json", std::process::id()));
+    fs::write(
+        &path,
+        r#"{
+            "version": 1,
+            "camera": { "pan": [0.0, 0.0], "scale": 1.0 },
+            "open_menus": ["markers"]
+        }"#,
+    )
+    .unwrap();
+
+    let mut state = MainState::new_headless(3).unwrap();
+    let robot = state.add_robot(1, 1);
+    state.update_world(grass_field(3));
+    state.load_session(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // characters missing from the font are left out of the menu
+    state.add_marker((1, 1), "café\n", Color::RGB(200, 30, 30), "home");
+    // and markers outside the map are listed as unknown
+    state.add_marker((50, 2), "far away", Color::RGB(30, 30, 200), "home");
+
+    state.update_robot(robot, Some((1, 2))).unwrap();
+    state.tick().unwrap();
+
+    assert_eq!(state.open_menus(), ["markers"]);
+    let labels = state
+        .get_markers()
+        .into_iter()
+        .map(|(_, marker)| marker.label().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["café\n", "far away"]);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{grass_field, tile};
+use robo_gui::{MainState, RobotId, SNAP_DISTANCE, TILE_SIZE};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+
+// with a robot speed of 3 a move takes TILE_SIZE / 4 frames
+const ROBOT_SPEED: i32 = 3;
+const FRAMES_PER_MOVE: i32 = TILE_SIZE / 4;
+
+fn world(size: usize, teleport: (usize, usize)) -> Vec<Vec<Option<Tile>>> {
+    let mut world = grass_field(size);
+    world[teleport.0][teleport.1] = tile(TileType::Teleport(true), Content::None);
+    world
+}
+
+fn drawn_at(state: &MainState, id: RobotId) -> (i32, i32) {
+    state.robot_position(id).unwrap()
+}
+
+fn tile_position((row, col): (usize, usize)) -> (i32, i32) {
+    (col as i32 * TILE_SIZE, row as i32 * TILE_SIZE)
+}
+
+#[test]
+fn robots_move_and_snap() {
+    let mut state = MainState::new_headless(ROBOT_SPEED).unwrap();
+    let robot = state.add_robot(1, 1);
+    state.update_world(world(12, (1, 4)));
+
+    // a single step is animated and ends exactly on the tile after FRAMES_PER_MOVE frames
+    state.update_robot(robot, Some((1, 2))).unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position((1, 1)));
+    for _ in 0..FRAMES_PER_MOVE - 1 {
+        state.render_frame().unwrap();
+        assert!(state.is_animating());
+    }
+    assert_ne!(drawn_at(&state, robot), tile_position((1, 2)));
+    state.render_frame().unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position((1, 2)));
+    assert!(!state.is_animating());
+    // and stays there
+    state.render_frame().unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position((1, 2)));
+
+    // jumping onto a teleport shows the robot there right away
+    state.update_robot(robot, Some((1, 4))).unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position((1, 4)));
+    assert!(!state.is_animating());
+
+    // so does a jump of more than SNAP_DISTANCE tiles
+    let far = (1, 4 + SNAP_DISTANCE as usize + 1);
+    state.update_robot(robot, Some(far)).unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position(far));
+    assert!(!state.is_animating());
+
+    // a jump of SNAP_DISTANCE tiles is still animated
+    let near = (1 + SNAP_DISTANCE as usize, far.1);
+    state.update_robot(robot, Some(near)).unwrap();
+    assert_eq!(drawn_at(&state, robot), tile_position(far));
+    for _ in 0..FRAMES_PER_MOVE {
+        state.render_frame().unwrap();
+    }
+    assert_eq!(drawn_at(&state, robot), tile_position(near));
+}
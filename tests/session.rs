@@ -0,0 +1,80 @@
+use std::env;
+use std::fs;
+
+use robo_gui::MainState;
+use sdl2::pixels::Color;
+
+fn described_markers(state: &MainState) -> Vec<((i32, i32), String, Color, String)> {
+    state
+        .get_markers()
+        .into_iter()
+        .map(|(coords, m)| {
+            let (label, category) = (m.label().to_string(), m.category().to_string());
+            (coords, label, m.color(), category)
+        })
+        .collect()
+}
+
+#[test]
+fn session_round_trip() {
+    let path = env::temp_dir().join(format!("robo_gui_session_{}.json", std::process::id()));
+
+    let mut state = MainState::new_headless(3).unwrap();
+    state.add_marker((1, 2), "base", Color::RGB(200, 30, 30), "home");
+    state.add_default_marker((4, 0));
+    state.save_session(&path).unwrap();
+    let saved = described_markers(&state);
+    // sdl allows a single event pump at a time, a state is dropped before the next is created
+    drop(state);
+
+    let mut restored = MainState::new_headless(3).unwrap();
+    restored.add_default_marker((7, 7));
+    restored.load_session(&path).unwrap();
+
+    assert_eq!(saved, described_markers(&restored));
+
+    // a session edited by hand, the camera and the menus are restored as well
+    fs::write(
+        &path,
+        r#"{
+            "version": 1,
+            "camera": { "pan": [12.5, -40.0], "scale": 2.0, "chase_robot": true },
+            "open_menus": ["markers"]
+        }"#,
+    )
+    .unwrap();
+    restored.load_session(&path).unwrap();
+    assert_eq!(restored.camera().pan(), (12.5, -40.0));
+    assert_eq!(restored.camera().scale(), 2.0);
+    assert!(restored.camera().is_chasing_robot());
+    assert_eq!(restored.open_menus(), ["markers"]);
+    assert!(restored.get_markers().is_empty());
+
+    // and written back unchanged
+    restored.add_marker((1, 2), "base", Color::RGB(200, 30, 30), "home");
+    restored.save_session(&path).unwrap();
+    let (pan, scale, menus) = (
+        restored.camera().pan(),
+        restored.camera().scale(),
+        restored.open_menus(),
+    );
+    let saved = described_markers(&restored);
+    drop(restored);
+    // no map is known yet, the open markers menu shows the marker as unknown
+    let mut reloaded = MainState::new_headless(3).unwrap();
+    reloaded.load_session(&path).unwrap();
+    assert_eq!(reloaded.camera().pan(), pan);
+    assert_eq!(reloaded.camera().scale(), scale);
+    assert!(reloaded.camera().is_chasing_robot());
+    assert_eq!(reloaded.open_menus(), menus);
+    assert_eq!(described_markers(&reloaded), saved);
+
+    // a broken file leaves the state untouched
+    fs::write(&path, "{ \"version\": 1 }").unwrap();
+    assert!(reloaded.load_session(&path).is_err());
+    assert_eq!(reloaded.get_markers().len(), 1);
+    assert_eq!(reloaded.camera().scale(), 2.0);
+    assert_eq!(reloaded.open_menus(), ["markers"]);
+
+    fs::remove_file(&path).unwrap();
+}